@@ -2,17 +2,15 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::game::Mk48Game;
-use crate::interpolated_contact::InterpolatedContact;
-use client_util::context::CoreState;
 use client_util::rate_limiter::RateLimiter;
 use client_util::renderer::particle::{Particle, ParticleLayer};
 use common::angle::Angle;
 use common::contact::{Contact, ContactTrait};
-use common::entity::{EntityData, EntityId, EntityKind, EntitySubKind};
+use common::entity::{EntityData, EntityKind, EntitySubKind};
+use common::protocol::CiwsBurst;
 use common_util::range::gen_radius;
 use glam::Vec2;
 use rand::{thread_rng, Rng};
-use std::collections::HashMap;
 
 impl Mk48Game {
     /// Finds the best armament (i.e. the one that will be fired if the mouse is clicked).
@@ -23,6 +21,7 @@ impl Mk48Game {
         angle_limit: bool,
         mouse_position: Vec2,
         armament_selection: Option<(EntityKind, EntitySubKind)>,
+        disabled_turrets: &[u8],
     ) -> Option<usize> {
         // The f32 represents how good the shot is, lower is better.
         let mut best_armament: Option<(usize, f32)> = None;
@@ -51,6 +50,10 @@ impl Mk48Game {
 
                 let mut max_angle_diff = Angle::ZERO;
                 if let Some(turret_index) = armament.turret {
+                    if disabled_turrets.contains(&(turret_index as u8)) {
+                        // Disabled by damage; cannot fire.
+                        continue;
+                    }
                     if !player_contact.data().turrets[turret_index]
                         .within_azimuth(player_contact.turrets()[turret_index])
                     {
@@ -74,10 +77,13 @@ impl Mk48Game {
                     || armament_entity_data.sub_kind == EntitySubKind::Depositor
                     || armament_entity_data.sub_kind == EntitySubKind::DepthCharge
                     || armament_entity_data.sub_kind == EntitySubKind::Mine
+                    || armament_entity_data.sub_kind == EntitySubKind::Jammer
+                    || armament_entity_data.sub_kind == EntitySubKind::Sonobuoy
                 {
                     // Vertically-launched armaments can fire in any horizontal direction.
                     // Aircraft can quickly assume any direction.
-                    // Depositors, depth charges, and mines are not constrained by direction.
+                    // Depositors, depth charges, mines, jammers, and sonobuoys are not constrained
+                    // by direction.
                     angle_diff = Angle::ZERO;
                 }
 
@@ -106,69 +112,34 @@ impl Mk48Game {
         best_armament.map(|(idx, _)| idx)
     }
 
-    /// This approximates the server-based automatic anti aircraft gunfire, in the form
-    /// of tracer particles and audio (return value is appropriate volume).
+    /// Renders tracer particles for bursts fired by server-side close-in weapon systems, and
+    /// returns an appropriate volume for the gunfire audio.
     pub fn simulate_anti_aircraft(
-        boat: &Contact,
-        contacts: &HashMap<EntityId, InterpolatedContact>,
-        core_state: &CoreState,
+        bursts: &[CiwsBurst],
         player_position: Vec2,
         airborne_particles: &mut ParticleLayer,
     ) -> f32 {
-        let mut volume = 0.0;
+        // Tracer particles per burst.
+        const TRACERS: usize = 12;
 
-        let data = boat.data();
+        let mut volume = 0.0;
         let mut rng = thread_rng();
-        // Anti-aircraft particles.
-        for InterpolatedContact {
-            view: aa_target, ..
-        } in contacts.values()
-        {
-            if aa_target.entity_type().map(|t| t.data().kind) != Some(EntityKind::Aircraft) {
-                // Not an aircraft.
-                continue;
-            }
-
-            let distance_squared = boat
-                .transform()
-                .position
-                .distance_squared(aa_target.transform().position);
-            if distance_squared > data.anti_aircraft_range().powi(2) {
-                // Out of range.
-                continue;
-            }
-
-            if rng.gen::<f32>() > data.anti_aircraft {
-                // Not powerful enough.
-                continue;
-            }
-
-            if core_state.are_friendly(boat.player_id(), aa_target.player_id()) {
-                // Don't shoot at friendly aircraft.
-                continue;
-            }
 
+        for burst in bursts {
             let time_of_flight = Particle::LIFESPAN * 0.6;
-            let mut prediction = *aa_target.transform();
-            prediction.do_kinematics(time_of_flight);
-            prediction.position += gen_radius(&mut rng, 10.0);
-
-            // Use current position not prediction, because that looks weird.
-            let aa_gun = boat
-                .transform()
-                .closest_point_on_keel_to(data.length * 0.8, aa_target.transform().position);
+            let target = burst.target + gen_radius(&mut rng, 10.0);
 
-            let vector = prediction.position - aa_gun;
+            let vector = target - burst.position;
             let distance = vector.length();
             if distance < 5.0 {
                 // Too close.
                 continue;
             }
             let normalized = vector / distance;
-            let offset = 5.0 + data.width * 0.4 + rng.gen::<f32>() * 10.0;
-            for i in 0..3 {
+            for i in 0..TRACERS {
+                let offset = 5.0 + rng.gen::<f32>() * 10.0 + i as f32;
                 airborne_particles.add(Particle {
-                    position: aa_gun + normalized * (offset + i as f32),
+                    position: burst.position + normalized * offset,
                     velocity: normalized * (distance.max(30.0) * (1.0 / time_of_flight))
                         + gen_radius(&mut rng, 1.0),
                     color: -1.0,
@@ -177,7 +148,7 @@ impl Mk48Game {
                 });
             }
 
-            volume += Self::volume_at(player_position.distance(aa_gun))
+            volume += Self::volume_at(player_position.distance(burst.position))
         }
 
         volume
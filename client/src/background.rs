@@ -8,11 +8,13 @@ use client_util::renderer::shader::{Shader, ShaderBinding};
 use client_util::renderer::texture::{Texture, TextureFormat};
 use common::entity::{EntityId, EntityType};
 use common::terrain::{Coord, RelativeCoord, Terrain};
+use common::time_of_day::TimeOfDay;
 use common::transform::Transform;
 use common::velocity::Velocity;
+use common::weather::Weather;
 use common::{terrain, world};
 use common_util::angle::{Angle, AngleRepr};
-use glam::{uvec2, vec2, vec3, Mat3, UVec2, Vec2};
+use glam::{uvec2, vec2, vec3, vec4, Mat3, UVec2, Vec2, Vec3};
 use std::convert::TryInto;
 
 #[derive(Copy, Clone, Default, Eq, PartialEq)]
@@ -231,6 +233,9 @@ pub struct Mk48OverlayContext {
     u_above: f32,
     u_area: f32,
     u_border: f32,
+    u_center: Vec2,
+    u_weather: Vec3,
+    u_daylight: f32,
     u_restrict: f32,
     u_visual: f32,
 }
@@ -241,6 +246,9 @@ impl Default for Mk48OverlayContext {
             u_above: 0.0,
             u_area: 0.0,
             u_border: 1000.0,
+            u_center: Vec2::ZERO,
+            u_weather: Vec3::ZERO,
+            u_daylight: 1.0,
             u_restrict: 0.0,
             u_visual: 0.0,
         }
@@ -253,11 +261,17 @@ impl Mk48OverlayContext {
         visual_range: f32,
         visual_restriction: f32,
         world_radius: f32,
+        world_center: Vec2,
         area: Option<(f32, bool)>,
+        weather: &Weather,
+        time_of_day: TimeOfDay,
     ) {
         self.u_visual = visual_range;
         self.u_restrict = visual_restriction;
         self.u_border = world_radius;
+        self.u_center = world_center;
+        self.u_weather = vec3(weather.fog, weather.rain, weather.storm);
+        self.u_daylight = time_of_day.daylight();
         self.u_above = area
             .as_ref()
             .map(|(_, above)| if *above { 1.0 } else { -1.0 })
@@ -279,7 +293,19 @@ impl BackgroundContext for Mk48OverlayContext {
             "uAbove_uArea_uBorder",
             vec3(self.u_above, self.u_area, self.u_border),
         );
-        shader.uniform2f("uRestrict_uVisual", vec2(self.u_restrict, self.u_visual));
+        shader.uniform4f(
+            "uFog_uRain_uStorm_uDaylight",
+            self.u_weather.extend(self.u_daylight),
+        );
+        shader.uniform4f(
+            "uRestrict_uVisual_uCenter",
+            vec4(
+                self.u_restrict,
+                self.u_visual,
+                self.u_center.x,
+                self.u_center.y,
+            ),
+        );
     }
 }
 
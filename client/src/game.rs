@@ -4,6 +4,7 @@
 use crate::armament::FireRateLimiter;
 use crate::audio::Audio;
 use crate::background::{Mk48BackgroundContext, Mk48OverlayContext};
+use crate::ghost::Ghost;
 use crate::interpolated::Interpolated;
 use crate::interpolated_contact::InterpolatedContact;
 use crate::settings::Mk48Settings;
@@ -14,7 +15,7 @@ use client_util::context::Context;
 use client_util::fps_monitor::FpsMonitor;
 use client_util::game_client::GameClient;
 use client_util::joystick::Joystick;
-use client_util::keyboard::Key;
+use client_util::keyboard::{Key, KeyboardEvent};
 use client_util::mouse::{MouseButton, MouseEvent};
 use client_util::rate_limiter::RateLimiter;
 use client_util::renderer::background::{BackgroundContext, BackgroundLayer};
@@ -30,11 +31,15 @@ use common::angle::Angle;
 use common::contact::{Contact, ContactTrait};
 use common::entity::{EntityData, EntityId, EntityKind, EntitySubKind, EntityType};
 use common::guidance::Guidance;
-use common::protocol::{Command, Control, Fire, Hint, Pay, Spawn, Update, Upgrade};
+use common::protocol::{
+    Command, Control, Fire, Hint, Lock, Pay, Route, Salvo, Spawn, Squadron, SquadronOrder, Update,
+    Upgrade,
+};
 use common::ticks::Ticks;
 use common::transform::Transform;
 use common::util::score_to_level;
 use common::velocity::Velocity;
+use common::weather::Weather;
 use common::world::strict_area_border;
 use common_util::range::{gen_radius, lerp, map_ranges};
 use core_protocol::id::{GameId, TeamId};
@@ -63,6 +68,14 @@ pub struct Mk48Game {
     pub zoom_input: f32,
     /// Last control, for diffing.
     pub last_control: Option<Control>,
+    /// Steered since the last control was sent, so guidance must be sent (cancelling any route).
+    steered: bool,
+    /// A waypoint should be added to the route, at the mouse position.
+    waypoint_requested: bool,
+    /// Key pressed to order the player's aircraft, which may depend on the mouse position.
+    squadron_requested: Option<Key>,
+    /// Weapons should lock on to the contact under the mouse, or release an existing lock.
+    lock_requested: bool,
     /// Rate limit control websocket messages.
     pub control_rate_limiter: RateLimiter,
     /// Rate limit ui props messages.
@@ -94,7 +107,7 @@ pub struct RendererLayer {
 }
 
 pub fn wind() -> Vec2 {
-    Vec2::new(7.0, 1.5)
+    common::weather::wind()
 }
 
 // Back 75 degrees is reverse angle.
@@ -142,6 +155,10 @@ impl GameClient for Mk48Game {
             saved_camera: None,
             respawn_overridden: false,
             last_control: None,
+            steered: false,
+            waypoint_requested: false,
+            squadron_requested: None,
+            lock_requested: false,
             control_rate_limiter: RateLimiter::new(0.1),
             ui_props_rate_limiter: RateLimiter::new(0.25),
             alarm_fast_rate_limiter: RateLimiter::new(10.0),
@@ -238,8 +255,13 @@ impl GameClient for Mk48Game {
 
                 // Compensate for the fact that the data is a little old (second parameter is rough
                 // estimate of latency)
-                network_contact.model.simulate(0.1);
+                network_contact
+                    .model
+                    .simulate(0.1, &context.state.game.terrain);
             } else {
+                // Found again.
+                context.state.game.ghosts.remove(id);
+
                 if play_sounds {
                     self.play_new_contact_audio(
                         contact,
@@ -271,8 +293,10 @@ impl GameClient for Mk48Game {
             .drain_filter(|id, InterpolatedContact { idle, view, .. }| {
                 if updated.contains_key(id) {
                     *idle = Ticks::ZERO;
-                    false
-                } else {
+                    return false;
+                }
+                // A sunk contact won't reappear, so don't wait for it to.
+                if !update.sunk.contains(id) {
                     *idle = idle.saturating_add(Ticks::ONE);
                     if *idle
                         <= view
@@ -283,15 +307,25 @@ impl GameClient for Mk48Game {
                         // Still in keep alive period.
                         return false;
                     }
-                    if Some(*id) == *entity_id {
-                        *entity_id = None;
-                    }
-                    true
                 }
+                if Some(*id) == *entity_id {
+                    *entity_id = None;
+                }
+                true
             })
             .map(|(_, InterpolatedContact { view, .. })| view)
             .collect::<Vec<_>>()
         {
+            if context.settings.contact_memory
+                && !context.state.core.is_friendly(contact.player_id())
+                && Ghost::is_memorable(&contact, update.sunk.contains(&contact.id()))
+            {
+                context.state.game.ghosts.insert(
+                    contact.id(),
+                    Ghost::new(&contact, context.client.update_seconds),
+                );
+            }
+
             if play_sounds {
                 let time_seconds = context.client.update_seconds;
                 self.play_lost_contact_audio_and_animations(
@@ -391,6 +425,20 @@ impl GameClient for Mk48Game {
         }
     }
 
+    fn peek_keyboard(&mut self, event: &KeyboardEvent, _context: &mut Context<Self>) {
+        if event.key == Key::Q && event.down {
+            // Mouse position is only known during tick.
+            self.waypoint_requested = true;
+        }
+        // Ctrl+F and Ctrl+V are left to the browser (search and paste).
+        if matches!(event.key, Key::B | Key::F | Key::V) && event.down && !event.ctrl {
+            self.squadron_requested = Some(event.key);
+        }
+        if event.key == Key::L && event.down {
+            self.lock_requested = true;
+        }
+    }
+
     fn peek_mouse(
         &mut self,
         event: &MouseEvent,
@@ -481,7 +529,11 @@ impl GameClient for Mk48Game {
 
             interp.update_error_bound(elapsed_seconds, debug_latency_entity_id);
             interp.generate_particles(layer);
-            interp.interpolate(elapsed_seconds, context.state.game.entity_id);
+            interp.interpolate(
+                elapsed_seconds,
+                context.state.game.entity_id,
+                &context.state.game.terrain,
+            );
         }
 
         // May have changed due to the above.
@@ -492,6 +544,9 @@ impl GameClient for Mk48Game {
         // TODO decouple update and render.
         renderer.set_camera(camera, zoom);
 
+        // Weather cells only move on the server, but they move slowly.
+        let weather = Weather::sample(&context.state.game.weather, camera);
+
         let (visual_range, visual_restriction, area) =
             if let Some(c) = context.state.game.player_interpolated_contact() {
                 // Use model as input to interpolation (can't interpolate twice).
@@ -503,7 +558,9 @@ impl GameClient for Mk48Game {
                 let entity_type = c.view.entity_type().unwrap();
 
                 let visual_range = entity_type.data().sensors.visual.range
-                    * map_ranges(altitude, -1.0..0.0, 0.4..0.8, true);
+                    * map_ranges(altitude, -1.0..0.0, 0.4..0.8, true)
+                    * weather.visual_factor
+                    * context.state.game.time_of_day.visual_factor();
                 let visual_restriction = map_ranges(altitude, 0.0..-1.0, 0.0..0.8, true);
                 let area = strict_area_border(entity_type);
                 (visual_range, visual_restriction, area)
@@ -528,10 +585,18 @@ impl GameClient for Mk48Game {
             visual_range,
             visual_restriction,
             context.state.game.world_radius,
+            context.state.game.world_center,
             area,
+            &weather,
+            context.state.game.time_of_day,
         );
 
-        let mut anti_aircraft_volume = 0.0;
+        // Tracers from bursts fired by close-in weapon systems.
+        let anti_aircraft_volume = Self::simulate_anti_aircraft(
+            &std::mem::take(&mut context.state.game.ciws_bursts),
+            renderer.camera_center(),
+            &mut layer.airborne_particles,
+        );
 
         // Update animations.
         let mut i = 0;
@@ -610,26 +675,23 @@ impl GameClient for Mk48Game {
                         );
                     }
 
-                    sortable_sprites.push(SortableSprite::new_entity(
-                        entity_id,
-                        entity_type,
-                        transform,
-                        altitude,
-                        alpha,
-                    ));
-                }
-
-                if contact.is_boat()
-                    && !contact.altitude().is_submerged()
-                    && data.anti_aircraft > 0.0
-                {
-                    anti_aircraft_volume += Self::simulate_anti_aircraft(
-                        contact,
-                        &context.state.game.contacts,
-                        &context.state.core,
-                        renderer.camera_center(),
-                        &mut layer.airborne_particles,
-                    );
+                    // Chaff has no sprite, since it is drawn as a cloud of particles.
+                    if data.sub_kind == EntitySubKind::Sonobuoy {
+                        // Neither do sonobuoys, which are drawn as floats.
+                        layer.graphics.add_filled_circle(
+                            transform.position,
+                            data.length.max(2.0),
+                            color.extend(alpha),
+                        );
+                    } else if data.sub_kind != EntitySubKind::Chaff {
+                        sortable_sprites.push(SortableSprite::new_entity(
+                            entity_id,
+                            entity_type,
+                            transform,
+                            altitude,
+                            alpha,
+                        ));
+                    }
                 }
 
                 if contact.is_boat() {
@@ -819,10 +881,17 @@ impl GameClient for Mk48Game {
                             // Turret azimuths.
                             // Pre-borrow to not borrow all of context (will be fixed eventually).
                             let ui_armament = context.ui.armament;
+                            let disabled_turrets = context.state.game.disabled_turrets();
                             if let Some((i, mouse_pos)) =
                                 context.mouse.world_position.and_then(|mouse_pos| {
-                                    self.find_best_armament(contact, false, mouse_pos, ui_armament)
-                                        .zip(Some(mouse_pos))
+                                    self.find_best_armament(
+                                        contact,
+                                        false,
+                                        mouse_pos,
+                                        ui_armament,
+                                        disabled_turrets,
+                                    )
+                                    .zip(Some(mouse_pos))
                                 })
                             {
                                 let armament = &data.armaments[i];
@@ -1008,7 +1077,7 @@ impl GameClient for Mk48Game {
                         }
 
                         // Name
-                        let text = if let Some(player) = context
+                        let mut text = if let Some(player) = context
                             .state
                             .core
                             .player_or_bot(contact.player_id().unwrap())
@@ -1026,6 +1095,17 @@ impl GameClient for Mk48Game {
                             format!("{}", contact.player_id().unwrap().0.get())
                         };
 
+                        // Credit whichever teammate spotted the contact.
+                        if let Some(spotter) = context
+                            .state
+                            .game
+                            .data_link
+                            .get(&contact.id())
+                            .and_then(|&id| context.state.core.player_or_bot(id))
+                        {
+                            text = format!("{} (via {})", text, spotter.alias);
+                        }
+
                         layer.text.add(
                             text,
                             contact.transform().position
@@ -1145,6 +1225,50 @@ impl GameClient for Mk48Game {
                         }
                     }
                 }
+
+                // Chaff blooms into a cloud of foil.
+                if data.sub_kind == EntitySubKind::Chaff {
+                    for _ in 0..amount * 2 {
+                        layer.airborne_particles.add(Particle {
+                            position: contact.transform().position + gen_radius(&mut rng, 12.0),
+                            velocity: gen_radius(&mut rng, 2.0),
+                            radius: 1.0,
+                            color: 0.8,
+                            smoothness: 0.5,
+                        });
+                    }
+                }
+
+                // Smoke from fires, and foam from flooding.
+                if contact.on_fire() {
+                    for _ in 0..amount {
+                        let forward = data.length * 0.3 * rng.gen_range(-1.0..1.0);
+                        layer.airborne_particles.add(Particle {
+                            position: contact.transform().position
+                                + direction_vector * forward
+                                + gen_radius(&mut rng, data.width * 0.25),
+                            velocity: gen_radius(&mut rng, 4.0),
+                            radius: 1.5,
+                            color: -1.0,
+                            smoothness: 1.0,
+                        });
+                    }
+                }
+                if contact.flooding() {
+                    for _ in 0..amount / 2 {
+                        let forward = data.length * 0.4 * rng.gen_range(-1.0..1.0);
+                        let side = data.width * 0.5 * rng.gen_range(-1.0..1.0);
+                        layer.sea_level_particles.add(Particle {
+                            position: contact.transform().position
+                                + direction_vector * forward
+                                + tangent_vector * side,
+                            velocity: gen_radius(&mut rng, 2.0),
+                            radius: 1.0,
+                            color: 1.0,
+                            smoothness: 1.0,
+                        });
+                    }
+                }
             } else {
                 layer.sprites.add(
                     "contact",
@@ -1157,6 +1281,103 @@ impl GameClient for Mk48Game {
             }
         }
 
+        // Last known positions of lost contacts.
+        if context.settings.contact_memory {
+            let time = context.client.update_seconds;
+            context
+                .state
+                .game
+                .ghosts
+                .retain(|_, ghost| !ghost.expired(time));
+
+            for ghost in context.state.game.ghosts.values() {
+                let (sprite, dimensions) = if let Some(entity_type) = ghost.entity_type {
+                    (entity_type.as_str(), entity_type.data().dimensions())
+                } else {
+                    ("contact", Vec2::splat(10.0))
+                };
+                layer.sprites.add(
+                    sprite,
+                    None,
+                    ghost.transform.position,
+                    dimensions,
+                    ghost.transform.direction,
+                    ghost.alpha(time),
+                );
+            }
+        } else {
+            context.state.game.ghosts.clear();
+        }
+
+        // Route, drawn as lines through the remaining waypoints.
+        if let Some((player_contact, route)) = context
+            .state
+            .game
+            .player_contact()
+            .zip(context.state.game.route.as_ref())
+        {
+            let color = if route.blocked {
+                rgba(255, 80, 80, 160)
+            } else {
+                rgba(255, 255, 255, 120)
+            };
+            let thickness = 0.002 * zoom;
+
+            let mut previous = player_contact.transform().position;
+            for &waypoint in &route.waypoints {
+                layer
+                    .graphics
+                    .add_line(previous, waypoint, thickness, color);
+                layer
+                    .graphics
+                    .add_circle(waypoint, 0.01 * zoom, thickness, color);
+                previous = waypoint;
+            }
+        }
+
+        // Locked contact, drawn as a reticle.
+        if let Some(contact) = context
+            .state
+            .game
+            .lock
+            .and_then(|id| context.state.game.contacts.get(&id))
+        {
+            let contact = &contact.view;
+            let radius = contact
+                .entity_type()
+                .map_or(30.0, |entity_type| entity_type.data().radius.max(30.0));
+            layer.graphics.add_circle(
+                contact.transform().position,
+                radius * 1.2,
+                0.003 * zoom,
+                rgba(255, 60, 60, 200),
+            );
+        }
+
+        // Weak passive sonar contacts, drawn as a bearing line and a circle around where the
+        // contact probably is.
+        if let Some(player_contact) = context.state.game.player_contact() {
+            let origin = player_contact.transform().position;
+            let color = rgba(255, 190, 90, 110);
+            let thickness = 0.002 * zoom;
+
+            for sonar_contact in &context.state.game.sonar_contacts {
+                let length = origin.distance(sonar_contact.position) + sonar_contact.radius;
+                layer.graphics.add_line(
+                    origin,
+                    origin + sonar_contact.bearing.to_vec() * length,
+                    thickness,
+                    color,
+                );
+                layer.graphics.add_circle(
+                    sonar_contact.position,
+                    sonar_contact.radius,
+                    thickness,
+                    color,
+                );
+            }
+        }
+
         context
             .state
             .game
@@ -1189,8 +1410,11 @@ impl GameClient for Mk48Game {
             .view_position
             .map(|p| renderer.to_world_position(p));
 
-        // Send command later, when lifetimes allow.
+        // Send commands later, when lifetimes allow.
         let mut control: Option<Command> = None;
+        let mut route: Option<Command> = None;
+        let mut squadron: Option<Command> = None;
+        let mut lock: Option<Command> = None;
 
         let status = if let Some(player_contact) = Self::maybe_contact_mut(
             &mut context.state.game.contacts,
@@ -1288,18 +1512,117 @@ impl GameClient for Mk48Game {
             if let Some(guidance) = guidance.as_ref() {
                 player_contact.model.predict_guidance(guidance);
                 player_contact.view.predict_guidance(guidance);
+                self.steered = true;
             }
 
             // Re-borrow as immutable.
             let player_contact = context.state.game.player_contact().unwrap();
 
+            if std::mem::take(&mut self.waypoint_requested) {
+                // Extend the existing route, if any.
+                let mut waypoints = context
+                    .state
+                    .game
+                    .route
+                    .as_ref()
+                    .map(|route| route.waypoints.clone())
+                    .unwrap_or_default();
+
+                if let Some(aim_target) =
+                    aim_target.filter(|_| waypoints.len() < Route::MAX_WAYPOINTS)
+                {
+                    waypoints.push(aim_target);
+
+                    // Keep the current speed, if moving forward.
+                    let velocity_target = player_contact.guidance().velocity_target;
+                    let speed = if velocity_target > Velocity::ZERO {
+                        velocity_target
+                    } else {
+                        player_contact.data().speed
+                    };
+
+                    route = Some(Command::Route(Route { waypoints, speed }));
+                    // Make sure that the next steering is sent, to cancel the route.
+                    self.last_control = None;
+                    self.steered = false;
+                }
+            }
+
+            if let Some(key) = self.squadron_requested.take() {
+                let order = match key {
+                    Key::B => Some(Some(SquadronOrder::Return)),
+                    // Follow the aim target again.
+                    Key::V => Some(None),
+                    _ => aim_target.map(|aim_target| {
+                        // Strike or escort the contact under the mouse, otherwise patrol there.
+                        Some(match context.state.game.contact_at(aim_target) {
+                            Some(contact)
+                                if !context.state.core.is_friendly(contact.player_id()) =>
+                            {
+                                SquadronOrder::Strike(contact.id())
+                            }
+                            Some(contact) if contact.is_boat() => {
+                                SquadronOrder::Escort(contact.id())
+                            }
+                            _ => SquadronOrder::CombatAirPatrol(aim_target),
+                        })
+                    }),
+                };
+
+                if let Some(order) = order {
+                    // Only order the selected type of aircraft, if any.
+                    let sub_kind = context
+                        .ui
+                        .armament
+                        .filter(|(kind, _)| *kind == EntityKind::Aircraft)
+                        .map(|(_, sub_kind)| sub_kind);
+                    squadron = Some(Command::Squadron(Squadron { sub_kind, order }));
+                }
+            }
+
+            if std::mem::take(&mut self.lock_requested) {
+                if context.state.game.lock.is_some() {
+                    lock = Some(Command::Lock(Lock { target: None }));
+                } else if let Some(contact) = aim_target
+                    .and_then(|aim_target| context.state.game.contact_at(aim_target))
+                    .filter(|contact| !context.state.core.is_friendly(contact.player_id()))
+                {
+                    lock = Some(Command::Lock(Lock {
+                        target: Some(contact.id()),
+                    }));
+                }
+            }
+
+            // Armaments on disabled turrets are shown as unavailable.
+            let disabled_turrets = context.state.game.disabled_turrets();
+            let armament_consumption = player_contact
+                .reloads()
+                .iter()
+                .zip(player_contact.data().armaments.iter())
+                .map(|(reloaded, armament)| {
+                    *reloaded
+                        && !armament
+                            .turret
+                            .map_or(false, |t| disabled_turrets.contains(&(t as u8)))
+                })
+                .collect();
+            let (engine_damage, sensor_damage) = context
+                .state
+                .game
+                .component_damage
+                .as_ref()
+                .map_or((0.0, 0.0), |damage| (damage.engine, damage.sensors));
+
             let status = UiStatus::Playing {
                 entity_type: player_contact.entity_type().unwrap(),
                 position: player_contact.transform().position.into(),
                 direction: player_contact.transform().direction,
                 velocity: player_contact.transform().velocity,
                 altitude: player_contact.altitude(),
-                armament_consumption: Some(player_contact.reloads().iter().map(|b| *b).collect()),
+                armament_consumption: Some(armament_consumption),
+                endurance: context.state.game.endurance,
+                engine_damage,
+                sensor_damage,
             };
 
             if self.control_rate_limiter.update_ready(elapsed_seconds) {
@@ -1310,45 +1633,63 @@ impl GameClient for Mk48Game {
                     aspect: renderer.aspect_ratio(),
                 });
 
+                let best_armament = if left_click
+                    || context
+                        .keyboard
+                        .state(Key::Space)
+                        .combined(context.keyboard.state(Key::E))
+                        .is_down()
+                {
+                    self.find_best_armament(
+                        player_contact,
+                        true,
+                        aim_target.unwrap_or_default(),
+                        context.ui.armament,
+                        context.state.game.disabled_turrets(),
+                    )
+                } else {
+                    None
+                };
+
+                // Holding shift fires the whole group at once.
+                let salvo_requested = context.keyboard.is_down(Key::Shift);
+
                 let current_control = Control {
-                    guidance: Some(*player_contact.guidance()), // TODO don't send if hasn't changed.
-                    submerge: if player_contact.data().sub_kind == EntitySubKind::Submarine {
-                        context.ui.altitude_target != Altitude::ZERO
+                    guidance: std::mem::take(&mut self.steered).then(|| *player_contact.guidance()),
+                    altitude_target: if player_contact.data().sub_kind == EntitySubKind::Submarine {
+                        context.ui.altitude_target
                     } else {
-                        false
+                        Altitude::ZERO
                     },
                     aim_target,
                     active: context.ui.active,
                     pay: context.keyboard.is_down(Key::C).then_some(Pay),
-                    fire: if left_click
-                        || context
-                            .keyboard
-                            .state(Key::Space)
-                            .combined(context.keyboard.state(Key::E))
-                            .is_down()
-                    {
-                        self.find_best_armament(
-                            player_contact,
-                            true,
-                            aim_target.unwrap_or_default(),
-                            context.ui.armament,
-                        )
-                        .map(|i| {
-                            self.fire_rate_limiter.fired(i as u8);
-
-                            Fire {
-                                armament_index: i as u8,
+                    fire: best_armament.filter(|_| !salvo_requested).map(|i| {
+                        self.fire_rate_limiter.fired(i as u8);
+
+                        Fire {
+                            armament_index: i as u8,
+                        }
+                    }),
+                    salvo: best_armament.filter(|_| salvo_requested).map(|i| {
+                        let armaments = &player_contact.data().armaments;
+                        for (j, armament) in armaments.iter().enumerate() {
+                            if armament.is_similar_to(&armaments[i]) {
+                                self.fire_rate_limiter.fired(j as u8);
                             }
-                        })
-                    } else {
-                        None
-                    },
+                        }
+
+                        Salvo {
+                            armament_index: i as u8,
+                            spread: Angle::from_degrees(context.settings.torpedo_spread),
+                        }
+                    }),
                     hint,
                 };
 
                 // Some things are not idempotent.
                 fn is_significant(control: &Control) -> bool {
-                    control.fire.is_some() || control.pay.is_some()
+                    control.fire.is_some() || control.salvo.is_some() || control.pay.is_some()
                 }
 
                 if Some(&current_control) != self.last_control.as_ref()
@@ -1386,6 +1727,18 @@ impl GameClient for Mk48Game {
             UiStatus::Spawning
         };
 
+        if let Some(route) = route {
+            context.send_to_game(route);
+        }
+
+        if let Some(squadron) = squadron {
+            context.send_to_game(squadron);
+        }
+
+        if let Some(lock) = lock {
+            context.send_to_game(lock);
+        }
+
         if let Some(control) = control {
             context.send_to_game(control);
         }
@@ -0,0 +1,126 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use common::contact::{Contact, ContactTrait};
+use common::entity::EntityType;
+use common::transform::Transform;
+use common_util::range::map_ranges;
+
+/// The last known position of a contact that is no longer detected, which fades over time.
+pub struct Ghost {
+    /// Type of the contact, if it was known.
+    pub entity_type: Option<EntityType>,
+    /// Position and heading when the contact was lost.
+    pub transform: Transform,
+    /// Time the contact was lost, in seconds.
+    lost: f32,
+}
+
+impl Ghost {
+    /// How long ghosts take to fade away, in seconds.
+    const LIFESPAN: f32 = 90.0;
+    /// Opacity of a freshly lost contact.
+    const MAX_ALPHA: f32 = 0.4;
+
+    pub fn new(contact: &Contact, time: f32) -> Self {
+        Self {
+            entity_type: contact.entity_type(),
+            transform: *contact.transform(),
+            lost: time,
+        }
+    }
+
+    /// Returns true if the lost contact is worth remembering, which it isn't if it sank.
+    pub fn is_memorable(contact: &Contact, sunk: bool) -> bool {
+        // Unknown contacts could be boats.
+        !sunk && (contact.entity_type().is_none() || contact.is_boat())
+    }
+
+    /// Opacity of the ghost, which decreases over time.
+    pub fn alpha(&self, time: f32) -> f32 {
+        map_ranges(
+            time - self.lost,
+            0.0..Self::LIFESPAN,
+            Self::MAX_ALPHA..0.0,
+            true,
+        )
+    }
+
+    pub fn expired(&self, time: f32) -> bool {
+        time - self.lost > Self::LIFESPAN
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ghost::Ghost;
+    use common::altitude::Altitude;
+    use common::contact::Contact;
+    use common::entity::{EntityId, EntityType};
+    use common::guidance::Guidance;
+    use common::ticks::Ticks;
+    use common::transform::Transform;
+    use glam::vec2;
+
+    fn contact(entity_type: Option<EntityType>) -> Contact {
+        Contact::new(
+            Altitude::ZERO,
+            Ticks::ZERO,
+            entity_type,
+            false,
+            Guidance::new(),
+            EntityId::new(1).unwrap(),
+            false,
+            None,
+            None,
+            Transform {
+                position: vec2(100.0, -50.0),
+                ..Transform::default()
+            },
+            None,
+        )
+    }
+
+    #[test]
+    fn create() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let boat = contact(Some(EntityType::Yamato));
+        assert!(Ghost::is_memorable(&boat, false));
+        assert!(!Ghost::is_memorable(&boat, true));
+        assert!(Ghost::is_memorable(&contact(None), false));
+        assert!(!Ghost::is_memorable(&contact(None), true));
+        assert!(!Ghost::is_memorable(
+            &contact(Some(EntityType::Mark18)),
+            false
+        ));
+
+        let ghost = Ghost::new(&boat, 10.0);
+        assert_eq!(ghost.entity_type, Some(EntityType::Yamato));
+        assert_eq!(ghost.transform.position, vec2(100.0, -50.0));
+    }
+
+    #[test]
+    fn fade() {
+        let ghost = Ghost::new(&contact(None), 10.0);
+        assert_eq!(ghost.alpha(10.0), Ghost::MAX_ALPHA);
+
+        let mut alpha = ghost.alpha(10.0);
+        for i in 1..=9 {
+            let faded = ghost.alpha(10.0 + i as f32 * 10.0);
+            assert!(faded < alpha, "{} < {}", faded, alpha);
+            alpha = faded;
+        }
+        assert_eq!(ghost.alpha(10.0 + Ghost::LIFESPAN), 0.0);
+    }
+
+    #[test]
+    fn expire() {
+        let ghost = Ghost::new(&contact(None), 10.0);
+        assert!(!ghost.expired(10.0));
+        assert!(!ghost.expired(10.0 + Ghost::LIFESPAN));
+        assert!(ghost.expired(10.0 + Ghost::LIFESPAN + 1.0));
+    }
+}
@@ -10,6 +10,7 @@ use client_util::renderer::particle::Particle;
 use common::contact::{Contact, ContactTrait};
 use common::entity::EntityId;
 use common::entity::{EntityData, EntityKind, EntitySubKind};
+use common::terrain::Terrain;
 use common::ticks::Ticks;
 use common_util::angle::Angle;
 use common_util::range::map_ranges;
@@ -169,7 +170,12 @@ impl InterpolatedContact {
     }
 
     /// Performs interpolation. Takes the entity id of the player's boat.
-    pub fn interpolate(&mut self, elapsed_seconds: f32, player_entity_id: Option<EntityId>) {
+    pub fn interpolate(
+        &mut self,
+        elapsed_seconds: f32,
+        player_entity_id: Option<EntityId>,
+        terrain: &Terrain,
+    ) {
         // Don't interpolate view's guidance if this is the player's boat, so that it doesn't jerk around.
         self.view.interpolate_towards(
             &self.model,
@@ -177,8 +183,8 @@ impl InterpolatedContact {
             elapsed_seconds * self.error,
             elapsed_seconds,
         );
-        self.model.simulate(elapsed_seconds);
-        self.view.simulate(elapsed_seconds);
+        self.model.simulate(elapsed_seconds, terrain);
+        self.view.simulate(elapsed_seconds, terrain);
     }
 }
 
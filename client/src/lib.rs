@@ -12,6 +12,7 @@ mod armament;
 mod audio;
 mod background;
 mod game;
+mod ghost;
 mod interpolated;
 mod interpolated_contact;
 mod settings;
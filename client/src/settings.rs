@@ -8,15 +8,22 @@ use client_util::setting::Settings;
 #[derive(Settings)]
 pub struct Mk48Settings {
     pub(crate) animations: bool,
+    /// Remember the last known positions of lost contacts.
+    pub(crate) contact_memory: bool,
     #[setting(range = "0..3")]
     pub(crate) wave_quality: u8,
+    /// Angle (in degrees) between adjacent torpedoes of a salvo.
+    #[setting(range = "0.0..10.0", finite)]
+    pub(crate) torpedo_spread: f32,
 }
 
 impl Default for Mk48Settings {
     fn default() -> Self {
         Self {
             animations: true,
+            contact_memory: true,
             wave_quality: 1,
+            torpedo_spread: 3.0,
         }
     }
 }
@@ -3,7 +3,8 @@ precision mediump float;
 varying vec2 vPosition;
 uniform vec4 uMiddle_uDerivative;
 uniform vec3 uAbove_uArea_uBorder;
-uniform vec2 uRestrict_uVisual;
+uniform vec4 uRestrict_uVisual_uCenter;
+uniform vec4 uFog_uRain_uStorm_uDaylight;
 
 float preciseLength(vec2 vec) {
     #define LENGTH_SCALE 64.0
@@ -12,7 +13,11 @@ float preciseLength(vec2 vec) {
 
 void main() {
     float area = (vPosition.y - uAbove_uArea_uBorder.y) * uAbove_uArea_uBorder.x;
-    float border = preciseLength(vPosition) - uAbove_uArea_uBorder.z;
+    float border = preciseLength(vPosition - uRestrict_uVisual_uCenter.zw) - uAbove_uArea_uBorder.z;
     gl_FragColor = mix(gl_FragColor, vec4(0.4, 0.15, 0.15, 1.0), clamp(max(border, area) * 0.1, 0.0, 0.5));
-    gl_FragColor = mix(gl_FragColor, vec4(0.0, 0.14, 0.32, 1.0), clamp((preciseLength(vPosition - uMiddle_uDerivative.xy) - uRestrict_uVisual.y) * 0.1, 0.0, uRestrict_uVisual.x));
+    gl_FragColor = mix(gl_FragColor, vec4(0.72, 0.76, 0.8, 1.0), uFog_uRain_uStorm_uDaylight.x * 0.45);
+    gl_FragColor = mix(gl_FragColor, vec4(0.2, 0.25, 0.3, 1.0), uFog_uRain_uStorm_uDaylight.y * 0.25);
+    gl_FragColor = mix(gl_FragColor, vec4(0.08, 0.08, 0.12, 1.0), uFog_uRain_uStorm_uDaylight.z * 0.4);
+    gl_FragColor = mix(gl_FragColor, vec4(0.01, 0.02, 0.08, 1.0), (1.0 - uFog_uRain_uStorm_uDaylight.w) * 0.6);
+    gl_FragColor = mix(gl_FragColor, vec4(0.0, 0.14, 0.32, 1.0), clamp((preciseLength(vPosition - uMiddle_uDerivative.xy) - uRestrict_uVisual_uCenter.y) * 0.1, 0.0, uRestrict_uVisual_uCenter.x));
 }
\ No newline at end of file
@@ -2,14 +2,21 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::animation::Animation;
+use crate::ghost::Ghost;
 use crate::interpolated_contact::InterpolatedContact;
 use crate::trail::TrailSystem;
 use client_util::apply::Apply;
-use common::contact::Contact;
+use common::contact::{Contact, ContactTrait};
 use common::death_reason::DeathReason;
 use common::entity::EntityId;
-use common::protocol::Update;
+use common::protocol::{
+    CapturePoint, CiwsBurst, ComponentDamage, RouteProgress, SonarContact, Update,
+};
 use common::terrain::Terrain;
+use common::time_of_day::TimeOfDay;
+use common::weather::WeatherCell;
+use core_protocol::id::PlayerId;
+use glam::Vec2;
 use std::collections::HashMap;
 
 /// State associated with game server connection. Reset when connection is reset.
@@ -18,10 +25,33 @@ pub struct Mk48State {
     pub contacts: HashMap<EntityId, InterpolatedContact>,
     pub death_reason: Option<DeathReason>,
     pub entity_id: Option<EntityId>,
+    /// Last known positions of lost contacts.
+    pub ghosts: HashMap<EntityId, Ghost>,
     pub score: u32,
     pub terrain: Terrain,
     pub trails: TrailSystem,
     pub world_radius: f32,
+    pub world_center: Vec2,
+    /// Capture points owned by teams (only in territory mode).
+    pub capture_points: Vec<CapturePoint>,
+    /// Weather cells near the player.
+    pub weather: Vec<WeatherCell>,
+    /// Weak passive sonar contacts, which are only approximately located.
+    pub sonar_contacts: Vec<SonarContact>,
+    /// Contacts that were only detected by teammates, and who spotted them.
+    pub data_link: HashMap<EntityId, PlayerId>,
+    /// Progress along the player's route, if any.
+    pub route: Option<RouteProgress>,
+    /// Fraction of submerged endurance remaining, if it is limited.
+    pub endurance: Option<f32>,
+    /// Components of the player's boat that are damaged, if any.
+    pub component_damage: Option<ComponentDamage>,
+    /// Bursts fired by close-in weapon systems that have yet to be rendered.
+    pub ciws_bursts: Vec<CiwsBurst>,
+    /// Contact the player's weapons are locked on to, if any.
+    pub lock: Option<EntityId>,
+    /// Current position in the day/night cycle.
+    pub time_of_day: TimeOfDay,
     terrain_reset: bool,
 }
 
@@ -32,11 +62,23 @@ impl Default for Mk48State {
             contacts: HashMap::new(),
             death_reason: None,
             entity_id: None,
+            ghosts: HashMap::new(),
             score: 0,
             terrain: Terrain::default(),
             trails: TrailSystem::default(),
             // Keep border off splash screen by assuming radius.
             world_radius: 10000.0,
+            world_center: Vec2::ZERO,
+            capture_points: Vec::new(),
+            weather: Vec::new(),
+            sonar_contacts: Vec::new(),
+            data_link: HashMap::new(),
+            route: None,
+            endurance: None,
+            component_damage: None,
+            ciws_bursts: Vec::new(),
+            lock: None,
+            time_of_day: TimeOfDay::default(),
             terrain_reset: false,
         }
     }
@@ -53,6 +95,26 @@ impl Mk48State {
         self.entity_id.map(|id| self.contacts.get(&id).unwrap())
     }
 
+    /// Returns the identified contact at a position (e.g. under the mouse), if any.
+    pub(crate) fn contact_at(&self, position: Vec2) -> Option<&Contact> {
+        self.contacts
+            .values()
+            .map(|contact| &contact.view)
+            .find(|contact| {
+                contact.entity_type().map_or(false, |entity_type| {
+                    contact.transform().position.distance_squared(position)
+                        < entity_type.data().radius.max(30.0).powi(2)
+                })
+            })
+    }
+
+    /// Returns the indices of the player's boat's turrets that are disabled by damage.
+    pub(crate) fn disabled_turrets(&self) -> &[u8] {
+        self.component_damage
+            .as_ref()
+            .map_or(&[], |damage| damage.disabled_turrets.as_slice())
+    }
+
     // Reset terrain cache when switching servers and state resets.
     // TODO find a better way to do this.
     pub fn take_terrain_reset(&mut self) -> bool {
@@ -74,6 +136,21 @@ impl Apply<Update> for Mk48State {
         self.terrain.apply_update(&update.terrain);
 
         self.world_radius = update.world_radius;
+        self.world_center = update.world_center;
+        self.capture_points = update.capture_points;
+        self.weather = update.weather;
+        self.sonar_contacts = update.sonar_contacts;
+        self.data_link = update
+            .data_link
+            .into_iter()
+            .map(|c| (c.entity_id, c.spotter))
+            .collect();
+        self.route = update.route;
+        self.endurance = update.endurance;
+        self.component_damage = update.component_damage;
+        self.ciws_bursts.extend(update.ciws_bursts);
+        self.lock = update.lock;
+        self.time_of_day = update.time_of_day;
         self.score = update.score;
     }
 
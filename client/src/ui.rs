@@ -109,6 +109,11 @@ pub enum UiStatus {
         altitude: Altitude,
         #[serde(skip_serializing_if = "Option::is_none")]
         armament_consumption: Option<Box<[bool]>>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        endurance: Option<f32>,
+        /// Fractions of max speed and sensor range lost to damage.
+        engine_damage: f32,
+        sensor_damage: f32,
     },
     #[serde(rename_all = "camelCase")]
     Respawning {
@@ -154,6 +159,8 @@ pub struct LeaderboardItemModel {
     pub name: PlayerAlias,
     pub team: Option<TeamName>,
     pub score: u32,
+    /// Number of capture points owned by the team (only in territory mode).
+    pub captures: usize,
 }
 
 #[derive(Serialize)]
@@ -265,10 +272,23 @@ impl Mk48Game {
                             .team_id
                             .and_then(|team_id| context.state.core.teams.get(&team_id))
                             .map(|team| team.name);
+                        let captures = player
+                            .team_id
+                            .map(|team_id| {
+                                context
+                                    .state
+                                    .game
+                                    .capture_points
+                                    .iter()
+                                    .filter(|point| point.owner == team_id)
+                                    .count()
+                            })
+                            .unwrap_or(0);
                         Some(LeaderboardItemModel {
                             name: player.alias,
                             team: team_name,
                             score: item.score,
+                            captures,
                         })
                     } else {
                         None
@@ -291,6 +311,7 @@ impl Mk48Game {
                                 name: item.alias,
                                 team: None,
                                 score: item.score,
+                                captures: 0,
                             })
                             .collect(),
                     )
@@ -30,6 +30,10 @@ impl Altitude {
     /// creates an unbalanced experience i.e. battleships and their non-homing torpedoes vs deep subs.
     pub const SPECIAL_OVERLAP_MARGIN: Altitude = Altitude(AltitudeRepr::MAX / 2);
 
+    /// Submarines at or above this altitude can see above the surface through their periscope, and
+    /// recharge their endurance through their snorkel.
+    pub const PERISCOPE_DEPTH: Altitude = Altitude(-5);
+
     pub fn new() -> Self {
         Self::ZERO
     }
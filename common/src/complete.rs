@@ -5,6 +5,7 @@ use crate::contact::*;
 use crate::death_reason::DeathReason;
 use crate::protocol::*;
 use crate::terrain::Terrain;
+use glam::Vec2;
 use std::mem;
 
 pub trait CompleteTrait<'a> {
@@ -25,6 +26,8 @@ pub trait CompleteTrait<'a> {
 
     fn world_radius(&self) -> f32;
 
+    fn world_center(&self) -> Vec2;
+
     fn terrain(&self) -> &Terrain;
 }
 
@@ -66,6 +69,11 @@ impl<'a> CompleteTrait<'a> for Complete<'a> {
         self.update.world_radius
     }
 
+    #[inline]
+    fn world_center(&self) -> Vec2 {
+        self.update.world_center
+    }
+
     #[inline]
     fn terrain(&self) -> &Terrain {
         self.terrain
@@ -5,6 +5,7 @@ use crate::altitude::Altitude;
 use crate::angle::Angle;
 use crate::entity::*;
 use crate::guidance::Guidance;
+use crate::terrain::Terrain;
 use crate::ticks::Ticks;
 use crate::transform::Transform;
 use crate::util::make_mut_slice;
@@ -28,10 +29,16 @@ pub trait ContactTrait {
 
     fn entity_type(&self) -> Option<EntityType>;
 
+    /// Whether the boat is flooding.
+    fn flooding(&self) -> bool;
+
     fn guidance(&self) -> &Guidance;
 
     fn id(&self) -> EntityId;
 
+    /// Whether the boat is on fire.
+    fn on_fire(&self) -> bool;
+
     fn player_id(&self) -> Option<PlayerId>;
 
     fn reloads(&self) -> &BitSlice<ReloadsStorage>;
@@ -65,7 +72,9 @@ pub struct Contact {
     guidance: Guidance,
     damage: Ticks,
     entity_type: Option<EntityType>,
+    flooding: bool,
     id: EntityId,
+    on_fire: bool,
     player_id: Option<PlayerId>,
     reloads: Option<BitArray<ReloadsStorage>>,
     turrets: Option<Arc<[Angle]>>,
@@ -77,8 +86,10 @@ impl Default for Contact {
             altitude: Altitude::default(),
             damage: Ticks::default(),
             entity_type: None,
+            flooding: false,
             guidance: Guidance::default(),
             id: EntityId::new(u32::MAX).unwrap(),
+            on_fire: false,
             player_id: None,
             reloads: None,
             transform: Transform::default(),
@@ -94,8 +105,10 @@ impl Contact {
         altitude: Altitude,
         damage: Ticks,
         entity_type: Option<EntityType>,
+        flooding: bool,
         guidance: Guidance,
         id: EntityId,
+        on_fire: bool,
         player_id: Option<PlayerId>,
         reloads: Option<BitArray<ReloadsStorage>>,
         transform: Transform,
@@ -105,8 +118,10 @@ impl Contact {
             altitude,
             damage,
             entity_type,
+            flooding,
             guidance,
             id,
+            on_fire,
             player_id,
             reloads,
             transform,
@@ -114,9 +129,9 @@ impl Contact {
         }
     }
 
-    /// Simulate delta_seconds passing, by updating guidance and kinematics. This is an approximation
-    /// of how the corresponding entity behaves on the server.
-    pub fn simulate(&mut self, delta_seconds: f32) {
+    /// Simulate delta_seconds passing, by updating guidance, kinematics, and drift due to ocean
+    /// currents. This is an approximation of how the corresponding entity behaves on the server.
+    pub fn simulate(&mut self, delta_seconds: f32, terrain: &Terrain) {
         if let Some(entity_type) = self.entity_type() {
             let guidance = *self.guidance();
             let max_speed = match entity_type.data().sub_kind {
@@ -140,6 +155,14 @@ impl Contact {
             );
         }
         self.transform_mut().do_kinematics(delta_seconds);
+
+        if let Some(entity_type) = self.entity_type() {
+            if !self.altitude().is_airborne() {
+                let position = self.transform().position;
+                self.transform_mut().position += terrain.current(position)
+                    * (entity_type.data().current_factor() * delta_seconds);
+            }
+        }
     }
 
     /// Interpolates or snaps one contact's fields to another, assuming they share the same id.
@@ -162,6 +185,8 @@ impl Contact {
 
         self.altitude = self.altitude.lerp(model.altitude, lerp);
         self.damage = model.damage;
+        self.flooding = model.flooding;
+        self.on_fire = model.on_fire;
         self.player_id = model.player_id;
         self.reloads = model.reloads;
         if interpolate_guidance {
@@ -235,6 +260,11 @@ impl ContactTrait for Contact {
         self.entity_type
     }
 
+    #[inline]
+    fn flooding(&self) -> bool {
+        self.flooding
+    }
+
     #[inline]
     fn guidance(&self) -> &Guidance {
         &self.guidance
@@ -245,6 +275,11 @@ impl ContactTrait for Contact {
         self.id
     }
 
+    #[inline]
+    fn on_fire(&self) -> bool {
+        self.on_fire
+    }
+
     #[inline]
     fn player_id(&self) -> Option<PlayerId> {
         self.player_id
@@ -290,10 +325,13 @@ struct ContactHeader {
     has_type: bool,
     has_player_id: bool,
     has_reloads: bool,
+    // Flags without corresponding elements.
+    on_fire: bool,
+    flooding: bool,
 }
 
 impl ContactHeader {
-    fn as_bits(&self) -> u8 {
+    fn as_bits(&self) -> u16 {
         let bools = [
             self.has_vel,
             self.has_alt,
@@ -303,22 +341,24 @@ impl ContactHeader {
             self.has_type,
             self.has_player_id,
             self.has_reloads,
+            self.on_fire,
+            self.flooding,
         ];
 
-        let mut bits: u8 = 0;
+        let mut bits: u16 = 0;
         for (i, &bit) in bools.iter().enumerate() {
-            bits |= (bit as u8) << i;
+            bits |= (bit as u16) << i;
         }
         bits
     }
 
-    fn from_bits(bits: u8) -> Self {
-        let mut bools = [false; 8];
+    fn from_bits(bits: u16) -> Self {
+        let mut bools = [false; 10];
         for (i, bit) in bools.iter_mut().enumerate() {
             *bit = bits & (1 << i) != 0
         }
 
-        let [has_vel, has_alt, has_dir_target, has_vel_target, has_damage, has_type, has_player_id, has_reloads] =
+        let [has_vel, has_alt, has_dir_target, has_vel_target, has_damage, has_type, has_player_id, has_reloads, on_fire, flooding] =
             bools;
 
         let header = Self {
@@ -330,13 +370,16 @@ impl ContactHeader {
             has_type,
             has_player_id,
             has_reloads,
+            on_fire,
+            flooding,
         };
         debug_assert_eq!(bits, header.as_bits());
         header
     }
 
     fn tuple_len(&self) -> usize {
-        12 - self.as_bits().count_zeros() as usize
+        // Only the low 8 bits have corresponding elements.
+        12 - (self.as_bits() as u8).count_zeros() as usize
     }
 }
 
@@ -376,6 +419,8 @@ impl<'a> ContactSerializer<'a> {
                 has_damage: c.damage != Ticks::ZERO,
                 has_player_id: c.player_id.is_some(),
                 has_reloads: c.reloads.is_some(),
+                on_fire: c.on_fire,
+                flooding: c.flooding,
             },
         };
 
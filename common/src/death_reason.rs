@@ -12,6 +12,7 @@ use std::cmp::Ordering;
 pub enum DeathReason {
     // For non-boats
     Landing(usize), // Contains index of armament aka landing pad.
+    Ditched,        // Aircraft ran out of fuel.
     // For boats and non-boats.
     Border,
     Terrain,
@@ -35,6 +36,7 @@ impl DeathReason {
             Self::Unknown => false,
             Self::Border => false,
             Self::Landing(_) => false,
+            Self::Ditched => false,
             Self::Terrain => false,
             Self::Boat(_) => true,
             Self::Obstacle(entity_type) => {
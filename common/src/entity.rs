@@ -7,7 +7,7 @@ use crate::contact::ReloadsStorage;
 use crate::ticks;
 use crate::ticks::Ticks;
 use crate::transform::Transform;
-use crate::util::{level_to_score, natural_death_coins};
+use crate::util::{lead_intercept, level_to_score, natural_death_coins};
 use crate::velocity::Velocity;
 use arrayvec::ArrayVec;
 use common_util::range::map_ranges_fast;
@@ -16,7 +16,7 @@ use enum_iterator::IntoEnumIterator;
 use glam::Vec2;
 use macros::entity_type;
 use rand::seq::IteratorRandom;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 use std::num::NonZeroU32;
@@ -64,6 +64,7 @@ impl EntityKind {
 pub enum EntitySubKind {
     Battleship,
     Carrier,
+    Chaff,
     Corvette,
     Cruiser,
     Depositor,
@@ -75,6 +76,7 @@ pub enum EntitySubKind {
     Hovercraft,
     Icebreaker,
     Gun,
+    Jammer,
     Lcs,
     Mine,
     Minelayer,
@@ -89,6 +91,7 @@ pub enum EntitySubKind {
     Score,
     Shell,
     Sonar,
+    Sonobuoy,
     Structure,
     Submarine,
     Tanker,
@@ -121,6 +124,12 @@ pub struct EntityData {
     pub draft: Altitude, // Type is a bit cheeky but kind of makes sense.
     #[serde(default)]
     pub depth: Altitude,
+    /// How long a submarine can stay below snorkel depth at low speed (zero means indefinitely).
+    #[serde(default)]
+    pub endurance: Ticks,
+    /// How long it takes to fully recharge endurance at the surface or snorkel depth.
+    #[serde(default)]
+    pub recharge: Ticks,
     #[serde(skip)]
     pub radius: f32,
     #[serde(skip)]
@@ -131,8 +140,10 @@ pub struct EntityData {
     pub anti_aircraft: f32,
     #[serde(default)]
     pub ram_damage: f32,
+    /// Fraction of damage resisted, by sub kind of what dealt it (including fires and flooding it
+    /// started). Ramming by any boat is resisted under [`EntitySubKind::Ram`].
     #[serde(default)]
-    pub torpedo_resistance: f32,
+    pub resistance: HashMap<EntitySubKind, f32>,
     #[serde(default)]
     pub stealth: f32,
     #[serde(default)]
@@ -184,6 +195,11 @@ impl EntityData {
         self.sensors.visual.range * 0.75
     }
 
+    /// Returns how fast (in radians per second) a boat can turn. Longer boats turn slower.
+    pub fn boat_turn_rate(&self) -> f32 {
+        0.125 + 20.0 / self.length
+    }
+
     /// Range of anti aircraft guns (whereas `self.anti_aircraft` is their power).
     pub fn anti_aircraft_range(&self) -> f32 {
         self.radii().end
@@ -200,10 +216,12 @@ impl EntityData {
 
     /// Returns multiplier for damage due to given sub kind.
     pub fn resistance_to_subkind(&self, sub_kind: EntitySubKind) -> f32 {
-        1.0 - match sub_kind {
-            EntitySubKind::Torpedo => self.torpedo_resistance,
-            _ => 0.0,
-        }
+        1.0 - self.resistance.get(&sub_kind).copied().unwrap_or(0.0)
+    }
+
+    /// Returns multiplier for damage due to being rammed, by any boat.
+    pub fn resistance_to_ram(&self) -> f32 {
+        self.resistance_to_subkind(EntitySubKind::Ram)
     }
 
     /// Returns minimum cavitation (making noisy bubbles) speed.
@@ -221,6 +239,21 @@ impl EntityData {
         )
     }
 
+    /// Returns how strongly ocean currents push this entity, which is stronger for small weapons
+    /// than large boats, and zero for stationary obstacles.
+    pub fn current_factor(&self) -> f32 {
+        match self.kind {
+            EntityKind::Boat => map_ranges_fast(self.length, 10.0..300.0, 1.0..0.25, true, true),
+            EntityKind::Weapon => match self.sub_kind {
+                EntitySubKind::Mine => 2.0,
+                EntitySubKind::Torpedo => 1.5,
+                _ => 1.0,
+            },
+            EntityKind::Obstacle => 0.0,
+            _ => 1.0,
+        }
+    }
+
     /// armament_transform returns the entity-relative transform of a given armament.
     pub fn armament_transform(&self, turret_angles: &[Angle], index: usize) -> Transform {
         let armament = &self.armaments[index];
@@ -257,12 +290,14 @@ impl EntityData {
         transform
     }
 
-    /// update_turret_aim brings turret_angles delta_seconds closer to position_target.
+    /// update_turret_aim brings turret_angles delta_seconds closer to position_target, leading it
+    /// by target_velocity (in meters per second) according to the speed of each turret's weapon.
     pub fn update_turret_aim(
         &self,
         boat_transform: Transform,
         turret_angles: &mut [Angle],
         position_target: Option<Vec2>,
+        target_velocity: Vec2,
         delta_seconds: f32,
     ) {
         for (i, a) in turret_angles.iter_mut().enumerate() {
@@ -278,6 +313,19 @@ impl EntityData {
                         direction: *a,
                         velocity: Velocity::ZERO,
                     };
+                let target = match self
+                    .armaments
+                    .iter()
+                    .find(|armament| armament.turret == Some(i))
+                {
+                    Some(armament) if target_velocity != Vec2::ZERO => lead_intercept(
+                        turret_global_transform.position,
+                        target,
+                        target_velocity,
+                        armament.entity_type.data().speed.to_mps(),
+                    ),
+                    _ => target,
+                };
                 let global_direction = Angle::from(target - turret_global_transform.position);
                 direction_target = global_direction - boat_transform.direction;
             }
@@ -305,6 +353,9 @@ pub struct Armament {
     pub external: bool,
     #[serde(default)]
     pub vertical: bool,
+    /// Whether the armament stays steerable by wire after being fired.
+    #[serde(default)]
+    pub wire: bool,
     #[serde(default)]
     pub position_forward: f32,
     #[serde(default)]
@@ -528,7 +579,12 @@ impl EntityType {
     }
 
     /// iterates all loot types entity should drop. Takes score before death.
-    pub fn loot(self, score: u32, score_to_coins: bool) -> impl Iterator<Item = Self> + 'static {
+    pub fn loot<R: Rng>(
+        self,
+        rng: &mut R,
+        score: u32,
+        score_to_coins: bool,
+    ) -> impl Iterator<Item = Self> + '_ {
         let data: &EntityData = self.data();
 
         debug_assert_eq!(data.kind, EntityKind::Boat);
@@ -539,8 +595,6 @@ impl EntityType {
             0
         };
 
-        let mut rng = thread_rng();
-
         // Loot is based on the length of the boat.
         let loot_amount = (data.length * 0.25 * (rng.gen::<f32>() * 0.1 + 0.9)) as u32;
 
@@ -565,7 +619,7 @@ impl EntityType {
             .map(move |_| {
                 *loot_table
                     .iter()
-                    .choose(&mut rng)
+                    .choose(&mut *rng)
                     .expect("at least once loot table option")
             })
             .chain((0..coin_amount).map(|_| Self::Coin))
@@ -599,7 +653,17 @@ impl EntityType {
 
 #[cfg(test)]
 mod tests {
-    use crate::entity::{EntityKind, EntityType};
+    use crate::entity::{EntityKind, EntitySubKind, EntityType};
+
+    #[test]
+    fn resistance_to_subkind() {
+        unsafe {
+            EntityType::init();
+        }
+        let data = EntityType::Yamato.data();
+        assert!((data.resistance_to_subkind(EntitySubKind::Torpedo) - 0.8).abs() < 0.001);
+        assert_eq!(data.resistance_to_subkind(EntitySubKind::Shell), 1.0);
+    }
 
     #[test]
     fn weapon_sensors() {
@@ -16,7 +16,9 @@ pub mod guidance;
 pub mod protocol;
 pub mod terrain;
 pub mod ticks;
+pub mod time_of_day;
 pub mod transform;
 pub mod util;
 pub mod velocity;
+pub mod weather;
 pub mod world;
@@ -1,11 +1,17 @@
 // SPDX-FileCopyrightText: 2021 Softbear, Inc.
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::altitude::Altitude;
+use crate::angle::Angle;
 use crate::contact::Contact;
 use crate::death_reason::DeathReason;
 use crate::entity::*;
 use crate::guidance::Guidance;
 use crate::terrain::{ChunkId, SerializedChunk};
+use crate::time_of_day::TimeOfDay;
+use crate::velocity::Velocity;
+use crate::weather::WeatherCell;
+use core_protocol::id::{PlayerId, TeamId};
 use glam::Vec2;
 use serde::{Deserialize, Serialize};
 
@@ -16,15 +22,99 @@ use serde::{Deserialize, Serialize};
 pub struct Update {
     /// All currently visible contacts.
     pub contacts: Vec<Contact>,
+    /// Submerged contacts that were only weakly detected by passive sonar.
+    pub sonar_contacts: Vec<SonarContact>,
+    /// Contacts that were only detected by teammates, and who spotted them.
+    pub data_link: Vec<DataLinkContact>,
+    /// Progress along the player's route, if they are following one.
+    pub route: Option<RouteProgress>,
+    /// Fraction of the player's submerged endurance remaining, if it is limited.
+    pub endurance: Option<f32>,
+    /// Components of the player's boat that are damaged, if any.
+    pub component_damage: Option<ComponentDamage>,
+    /// Bursts fired by close-in weapon systems in view since the last update.
+    pub ciws_bursts: Vec<CiwsBurst>,
+    /// Boats in view that sank since the last update, as opposed to being lost.
+    pub sunk: Vec<EntityId>,
+    /// Contact the player's weapons are locked on to, if any.
+    pub lock: Option<EntityId>,
     /// Why the player died, if they died, otherwise None.
     pub death_reason: Option<DeathReason>,
     /// Player's current score.
     pub score: u32,
     /// Current world border radius.
     pub world_radius: f32,
+    /// Current world border center (only moves away from the origin in battle royale mode).
+    pub world_center: Vec2,
+    /// Capture points owned by teams (only in territory mode).
+    pub capture_points: Vec<CapturePoint>,
+    /// Weather cells that overlap the player's view.
+    pub weather: Vec<WeatherCell>,
+    /// Current position in the day/night cycle.
+    pub time_of_day: TimeOfDay,
     pub terrain: Box<TerrainUpdate>,
 }
 
+/// A HQ or oil platform, owned by a team in territory mode.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CapturePoint {
+    pub entity_id: EntityId,
+    pub owner: TeamId,
+    /// Whether the owner currently has boats in range (and no one else does), and is therefore
+    /// being paid.
+    pub held: bool,
+}
+
+/// A submerged contact that was only weakly detected by passive sonar, so its position is only
+/// approximately known.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct SonarContact {
+    /// Direction from the observer to the actual position.
+    pub bearing: Angle,
+    /// Estimated position, which is offset from the actual position (consistently, for a while).
+    pub position: Vec2,
+    /// Radius, around the estimated position, in which the actual position lies.
+    pub radius: f32,
+}
+
+/// A contact that was shared by a teammate, instead of being detected by the player's own sensors.
+/// Such contacts have reduced fidelity (no turret angles or reload state).
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct DataLinkContact {
+    pub entity_id: EntityId,
+    /// Teammate that detected the contact.
+    pub spotter: PlayerId,
+}
+
+/// A burst fired by a close-in weapon system at an incoming aircraft, missile, or rocket.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct CiwsBurst {
+    /// Position of the gun.
+    pub position: Vec2,
+    /// Position of the target.
+    pub target: Vec2,
+}
+
+/// Damaged components of a boat, which are repaired over time.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct ComponentDamage {
+    /// Indices of turrets that are disabled.
+    pub disabled_turrets: Vec<u8>,
+    /// Fraction of max speed lost to engine damage.
+    pub engine: f32,
+    /// Fraction of sensor range lost to sensor damage.
+    pub sensors: f32,
+}
+
+/// Progress of a boat along its route.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct RouteProgress {
+    /// Waypoints that have yet to be reached, in order.
+    pub waypoints: Vec<Vec2>,
+    /// Whether the boat stopped because of a terrain hazard ahead.
+    pub blocked: bool,
+}
+
 /// Updates for terrain chunks.
 pub type TerrainUpdate = [(ChunkId, SerializedChunk)];
 
@@ -36,21 +126,26 @@ pub enum Command {
     Control(Control),
     Spawn(Spawn),
     Upgrade(Upgrade),
+    Route(Route),
+    Squadron(Squadron),
+    Lock(Lock),
 }
 
 /// Generic command to control one's ship.
 #[derive(Clone, Serialize, PartialEq, Deserialize, Debug)]
 pub struct Control {
-    /// Steering commands.
+    /// Steering commands (cancels any route).
     pub guidance: Option<Guidance>,
-    /// Submerge submarine.
-    pub submerge: bool,
+    /// Target altitude of submarine, which is clamped to its depth (zero means surfaced).
+    pub altitude_target: Altitude,
     /// Turret/aircraft/pay target.
     pub aim_target: Option<Vec2>,
     /// Active sensors.
     pub active: bool,
     /// Fire weapon a weapon.
     pub fire: Option<Fire>,
+    /// Fire a group of weapons at once.
+    pub salvo: Option<Salvo>,
     /// Pay one coin.
     pub pay: Option<Pay>,
     /// Optional hints.
@@ -64,6 +159,22 @@ pub struct Fire {
     pub armament_index: u8,
 }
 
+/// Fire/use all ready armaments similar to one (see `Armament::is_similar_to`), such as a fan of
+/// torpedoes or a broadside. Armaments without room to launch are left out, so a salvo may be
+/// partial.
+#[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
+pub struct Salvo {
+    /// The index of any weapon of the group, relative to `EntityData.armaments`.
+    pub armament_index: u8,
+    /// Angle between adjacent torpedoes, which fan out around the aim (ignored for other weapons).
+    pub spread: Angle,
+}
+
+impl Salvo {
+    /// Maximum spread (in degrees) between adjacent torpedoes.
+    pub const MAX_SPREAD_DEGREES: f32 = 10.0;
+}
+
 /// Provide hints to optimize experience.
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 pub struct Hint {
@@ -78,17 +189,71 @@ impl Default for Hint {
     }
 }
 
+/// Locks weapons on to a contact, such that turrets and launched weapons lead it for as long as it
+/// stays detected.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Lock {
+    /// Contact to lock on to, or None to release the lock.
+    pub target: Option<EntityId>,
+}
+
 /// Pay one coin. TODO: Can't use Option<empty struct>, as serde_json serializes both [`None`] and
 /// [`Some`] to `"null"`.
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
 pub struct Pay;
 
+/// Automatically steer through waypoints, until manually steered with `Control::guidance`.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Route {
+    /// Positions to steer through, in order. Empty cancels any existing route.
+    pub waypoints: Vec<Vec2>,
+    /// Speed to travel at.
+    pub speed: Velocity,
+}
+
+impl Route {
+    pub const MAX_WAYPOINTS: usize = 8;
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Spawn {
     /// What to spawn as. Must be an affordable boat.
     pub entity_type: EntityType,
 }
 
+/// Orders a group (squadron) of the player's launched aircraft, which otherwise fly towards the
+/// player's aim target.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct Squadron {
+    /// Which aircraft (e.g. planes or helicopters) to order, or all of them if None.
+    pub sub_kind: Option<EntitySubKind>,
+    /// What to do, or None to follow the aim target again.
+    pub order: Option<SquadronOrder>,
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub enum SquadronOrder {
+    /// Circle around a position, engaging anything that comes near.
+    CombatAirPatrol(Vec2),
+    /// Attack a contact detected by the player's own boat (not one only seen through teammates or
+    /// sonobuoys).
+    Strike(EntityId),
+    /// Circle around a friendly boat.
+    Escort(EntityId),
+    /// Return to land on the launcher.
+    Return,
+}
+
+impl SquadronOrder {
+    /// Returns the contact the order refers to, if any.
+    pub fn target(&self) -> Option<EntityId> {
+        match *self {
+            Self::Strike(id) | Self::Escort(id) => Some(id),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize, Debug)]
 pub struct Upgrade {
     /// What to upgrade to. Must be an affordable boat of higher level.
@@ -131,11 +296,13 @@ mod tests {
                 Altitude::from_u8(rng.gen()),
                 Ticks::from_secs(rng.gen::<f32>() * 10.0),
                 entity_type,
+                is_boat && rng.gen_bool(0.5),
                 Guidance {
                     direction_target: rng.gen(),
                     velocity_target: Velocity::from_mps(rng.gen::<f32>() * 3.0),
                 },
                 EntityId::new(rng.gen_range(1..u32::MAX)).unwrap(),
+                is_boat && rng.gen_bool(0.5),
                 rng.gen_bool(0.5)
                     .then(|| PlayerId(NonZeroU32::new(rng.gen_range(1..u32::MAX)).unwrap())),
                 (is_boat && rng.gen_bool(0.5)).then(|| {
@@ -3,23 +3,23 @@
 
 use crate::altitude::Altitude;
 use crate::protocol::TerrainUpdate;
+use crate::ticks::Ticks;
 use crate::transform::DimensionTransform;
 use crate::world;
 use common_util::range::lerp;
 use fast_hilbert as hilbert;
 use glam::Vec2;
 use lazy_static::lazy_static;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::{Debug, Formatter};
 use std::mem::{size_of, transmute};
-use std::ops::{Add, Mul, RangeInclusive, Sub};
+use std::ops::{Add, Mul, Range, RangeInclusive, Sub};
 use std::sync::atomic::{AtomicPtr, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
-use std::time::{Duration, Instant};
 
 // Scale of terrain aka meters per pixel.
 pub const SCALE: f32 = 25.0;
@@ -228,11 +228,20 @@ impl TryFrom<Vec2> for ChunkId {
 
 type Generator = fn(usize, usize) -> u8;
 
+/// Returns the ocean current of a chunk, in meters per second.
+type CurrentGenerator = fn(ChunkId) -> Vec2;
+
 /// Always returns zero. For placeholder purposes, or when no generator is required.
 fn zero_generator(_: usize, _: usize) -> u8 {
     0
 }
 
+/// Always returns no current. For placeholder purposes, or when currents are received from the
+/// server.
+fn zero_current_generator(_: ChunkId) -> Vec2 {
+    Vec2::ZERO
+}
+
 /// Terrain stores a bitmap representing the altitude at each pixel in a 2D grid.
 pub struct Terrain {
     chunks: [[Option<Box<Chunk>>; SIZE_CHUNKS]; SIZE_CHUNKS],
@@ -243,6 +252,7 @@ pub struct Terrain {
     /// Guards chunk generation.
     mutex: Mutex<()>,
     generator: Generator,
+    current_generator: CurrentGenerator,
 }
 
 pub struct TerrainMutation {
@@ -321,6 +331,12 @@ impl Terrain {
 
     /// Allocates a Terrain with a custom generator, but does not actually generate any chunks.
     pub fn with_generator(generator: Generator) -> Self {
+        Self::with_generators(generator, zero_current_generator)
+    }
+
+    /// Allocates a Terrain with custom terrain and current generators, but does not actually
+    /// generate any chunks.
+    pub fn with_generators(generator: Generator, current_generator: CurrentGenerator) -> Self {
         const NONE_CHUNK: Option<Box<Chunk>> = None;
         const NONE_CHUNK_ROW: [Option<Box<Chunk>>; SIZE_CHUNKS] = [NONE_CHUNK; SIZE_CHUNKS];
 
@@ -329,6 +345,7 @@ impl Terrain {
             updated: ChunkSet::new(),
             mutex: Mutex::new(()),
             generator,
+            current_generator,
         }
     }
 
@@ -342,11 +359,24 @@ impl Terrain {
     pub fn mut_chunk(&mut self, chunk_id: ChunkId) -> &mut Chunk {
         let chunk = &mut self.chunks[chunk_id.1 as usize][chunk_id.0 as usize];
         if chunk.is_none() {
-            *chunk = Some(Chunk::new(chunk_id, self.generator));
+            *chunk = Some(Self::generate_chunk(
+                chunk_id,
+                self.generator,
+                self.current_generator,
+            ));
         }
         chunk.as_mut().unwrap()
     }
 
+    /// Replaces a chunk with one encoded by `Chunk::to_bytes`, which doesn't include the ocean
+    /// current, so it is generated as usual.
+    pub fn load_chunk(&mut self, chunk_id: ChunkId, bytes: &[u8]) {
+        let current = (self.current_generator)(chunk_id);
+        let chunk = self.mut_chunk(chunk_id);
+        *chunk = Chunk::from_bytes(bytes);
+        chunk.current = current;
+    }
+
     /// Gets a reference to a chunk, generating it if necessary.
     #[inline]
     pub fn get_chunk(&self, chunk_id: ChunkId) -> &Chunk {
@@ -368,12 +398,35 @@ impl Terrain {
         }
 
         // TODO generate in parallel.
-        let chunk = Box::into_raw(Chunk::new(chunk_id, self.generator));
+        let chunk = Box::into_raw(Self::generate_chunk(
+            chunk_id,
+            self.generator,
+            self.current_generator,
+        ));
         ptr.store(chunk, Ordering::Release);
         drop(lock);
         chunk.as_ref().unwrap()
     }
 
+    fn generate_chunk(
+        chunk_id: ChunkId,
+        generator: Generator,
+        current_generator: CurrentGenerator,
+    ) -> Box<Chunk> {
+        let mut chunk = Chunk::new(chunk_id, generator);
+        chunk.current = current_generator(chunk_id);
+        chunk
+    }
+
+    /// Iterates all chunks that have been generated so far.
+    pub fn iter_chunks(&self) -> impl Iterator<Item = (ChunkId, &Chunk)> + '_ {
+        self.chunks.iter().enumerate().flat_map(|(cy, chunks)| {
+            chunks.iter().enumerate().filter_map(move |(cx, chunk)| {
+                chunk.as_deref().map(|chunk| (ChunkId(cx as u16, cy as u16), chunk))
+            })
+        })
+    }
+
     /// Applies a terrain update, overwriting relevant terrain pixels.
     pub fn apply_update(&mut self, update: &TerrainUpdate) {
         for (chunk_id, serialized) in update.iter() {
@@ -437,6 +490,29 @@ impl Terrain {
         }
     }
 
+    /// Gets the ocean current at a position, in meters per second, interpolated between the
+    /// centers of neighboring chunks.
+    pub fn current(&self, pos: Vec2) -> Vec2 {
+        // Chunk space, in which chunk centers lie on whole numbers.
+        let pos = pos.mul(1.0 / (SCALE * CHUNK_SIZE as f32)) + (CHUNK_OFFSET as f32 - 0.5);
+        let f_pos = pos.floor();
+
+        let at = |x: f32, y: f32| {
+            const RANGE: Range<f32> = 0.0..SIZE_CHUNKS as f32;
+            if RANGE.contains(&x) && RANGE.contains(&y) {
+                self.get_chunk(ChunkId(x as u16, y as u16)).current
+            } else {
+                Vec2::ZERO
+            }
+        };
+
+        let delta = pos.sub(f_pos);
+        let (fx, fy) = (f_pos.x, f_pos.y);
+        let bottom = at(fx, fy).lerp(at(fx + 1.0, fy), delta.x);
+        let top = at(fx, fy + 1.0).lerp(at(fx + 1.0, fy + 1.0), delta.x);
+        bottom.lerp(top, delta.y)
+    }
+
     /// Gets the smoothed Altitude at a position.
     pub fn sample(&self, pos: Vec2) -> Option<Altitude> {
         let pos = pos.mul(1.0 / SCALE);
@@ -479,6 +555,21 @@ impl Terrain {
         ) as u8))
     }
 
+    /// Returns true if the straight line between two positions doesn't cross terrain above sand
+    /// level, excluding the positions themselves (which may be on the shore).
+    pub fn line_of_sight(&self, from: Vec2, to: Vec2) -> bool {
+        let delta = to - from;
+
+        // March roughly one terrain pixel at a time.
+        let steps = (delta.length() * (1.0 / SCALE)) as u32;
+        let step = delta / steps.max(1) as f32;
+
+        (1..steps).all(|i| {
+            self.sample(from + step * i as f32)
+                .map_or(true, |altitude| altitude <= SAND_LEVEL)
+        })
+    }
+
     /// collides_with returns one point (and the altitude there) of collision if an entity collides
     /// with the terrain any time in the next delta_seconds.
     pub fn collides_with(
@@ -640,12 +731,12 @@ impl Terrain {
         }
     }
 
-    /// post_update is called once after all clients recieve updates each tick.
-    pub fn post_update(&mut self) {
+    /// post_update is called once after all clients recieve updates each tick, delta after the
+    /// last call. Regeneration is scheduled with the world's rng, so it is deterministic.
+    pub fn post_update(&mut self, delta: Ticks, rng: &mut impl Rng) {
         // Reset updated
         self.updated = ChunkSet::new();
 
-        let now = Instant::now();
         for (cy, chunks) in self.chunks.iter_mut().enumerate() {
             for (cx, chunk) in chunks.iter_mut().enumerate() {
                 if let Some(chunk) = chunk {
@@ -655,8 +746,16 @@ impl Terrain {
                     chunk.update = ChunkUpdate::None;
 
                     // Regenerate applicable chunks.
-                    if let Some(next_regen) = chunk.next_regen {
-                        if now >= next_regen {
+                    match chunk.regen {
+                        Regen::None => {}
+                        Regen::Pending => {
+                            let secs = rng.gen_range(0.75..1.25) * Chunk::REGEN_SECS;
+                            chunk.regen = Regen::In(Ticks::from_secs(secs));
+                        }
+                        Regen::In(remaining) if remaining > delta => {
+                            chunk.regen = Regen::In(remaining - delta);
+                        }
+                        Regen::In(_) => {
                             let chunk_id = ChunkId(cx as u16, cy as u16);
                             chunk.regenerate(chunk_id, self.generator); // TODO parallelize
 
@@ -780,21 +879,39 @@ impl Default for ChunkUpdate {
 pub struct SerializedChunk {
     is_update: bool,
     bytes: Arc<[u8]>, // TODO: use serde_bytes.
+    current: Vec2,
+}
+
+/// When a modified chunk regenerates.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Regen {
+    /// Unmodified, so nothing to regenerate.
+    None,
+    /// Modified, but not scheduled until the next `Terrain::post_update`.
+    Pending,
+    /// Regenerates after this many more ticks.
+    In(Ticks),
 }
 
 /// A single chunk in a Terrain.
 pub struct Chunk {
     data: [[u8; CHUNK_SIZE / 2]; CHUNK_SIZE],
-    next_regen: Option<Instant>,
+    /// Ocean current at the center of the chunk, in meters per second.
+    current: Vec2,
+    regen: Regen,
     update: ChunkUpdate,
 }
 
 impl Chunk {
+    /// Average delay between regenerations of a modified chunk, in seconds.
+    const REGEN_SECS: f32 = 60.0 * 20.0;
+
     /// Allocates a zero chunk.
     pub fn zero() -> Self {
         Self {
             data: [[0; CHUNK_SIZE / 2]; CHUNK_SIZE],
-            next_regen: None,
+            current: Vec2::ZERO,
+            regen: Regen::None,
             update: ChunkUpdate::None,
         }
     }
@@ -846,7 +963,7 @@ impl Chunk {
             }
         }
 
-        self.next_regen = None;
+        self.regen = Regen::None;
 
         if incomplete {
             self.mark_for_regenerate();
@@ -856,11 +973,8 @@ impl Chunk {
     /// mark_for_regenerate marks this chunk for regenerating after a standard time delay.
     /// Does nothing if the chunk is already marked as such.
     fn mark_for_regenerate(&mut self) {
-        if self.next_regen.is_none() {
-            self.next_regen = Some(
-                Instant::now()
-                    + Duration::from_secs_f32(thread_rng().gen_range(0.75..1.25) * 60.0 * 20.0),
-            );
+        if self.regen == Regen::None {
+            self.regen = Regen::Pending;
         }
     }
 
@@ -973,6 +1087,7 @@ impl Chunk {
                     return SerializedChunk {
                         is_update: true,
                         bytes: Arc::clone(mods),
+                        current: self.current,
                     }
                 }
                 ChunkUpdate::Complete => (),
@@ -983,6 +1098,7 @@ impl Chunk {
         SerializedChunk {
             is_update: false,
             bytes: self.to_bytes().into(), // TODO could save encoded chunk is lru cache but would require atomics.
+            current: self.current,
         }
     }
 
@@ -1006,7 +1122,8 @@ impl Chunk {
             // Overwrite chunk.
             *self = Self::from_bytes(bytes);
             ChunkUpdate::Complete
-        }
+        };
+        self.current = serialized.current;
     }
 
     /// Returns an iterator of rects that cover the updated portion of the chunk.
@@ -1354,7 +1471,8 @@ impl Iterator for Decompressor<'_> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use rand::{thread_rng, Rng};
+    use rand::thread_rng;
+    use std::time::Instant;
 
     fn random_generator(_: usize, _: usize) -> u8 {
         thread_rng().gen::<u8>() & 0b11110000
@@ -1457,6 +1575,89 @@ mod tests {
         assert_eq!(terrain.sample(pos).unwrap(), Altitude(-1));
     }
 
+    #[test]
+    fn current() {
+        fn current_generator(chunk_id: ChunkId) -> Vec2 {
+            Vec2::new(chunk_id.0 as f32, 0.0)
+        }
+
+        let terrain = Terrain::with_generators(zero_generator, current_generator);
+        let chunk_id = ChunkId(CHUNK_OFFSET as u16, CHUNK_OFFSET as u16);
+        let center = chunk_id.as_position();
+        let x = chunk_id.0 as f32;
+
+        assert_eq!(terrain.current(center), Vec2::new(x, 0.0));
+
+        // Halfway between chunk centers.
+        let half = SCALE * CHUNK_SIZE as f32 * 0.5;
+        let between = terrain.current(center + Vec2::new(half, 0.0));
+        assert!((between.x - (x + 0.5)).abs() < 0.01, "{:?}", between);
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let mut terrain = Terrain::with_generator(zero_generator);
+
+        let a = Vec2::new(-500.0, 0.0);
+        let b = Vec2::new(500.0, 0.0);
+        assert!(terrain.line_of_sight(a, b));
+
+        // Raise an island in the middle.
+        for _ in 0..5 {
+            terrain.modify(TerrainMutation::simple(Vec2::ZERO, 50.0));
+        }
+        assert!(terrain.sample(Vec2::ZERO).unwrap() > SAND_LEVEL);
+
+        assert!(!terrain.line_of_sight(a, b));
+        assert!(!terrain.line_of_sight(b, a));
+        assert!(terrain.line_of_sight(a, Vec2::new(-500.0, 500.0)));
+    }
+
+    /// Measures the cost of line of sight over sensor range, in the worst case of open water (which
+    /// has to be marched all the way). Run with
+    /// `cargo test --release -- --ignored line_of_sight_benchmark`.
+    #[test]
+    #[ignore]
+    fn line_of_sight_benchmark() {
+        let terrain = Terrain::with_generator(zero_generator);
+        let mut rng = thread_rng();
+
+        const CHECKS: u32 = 100000;
+        const RANGE: f32 = 1500.0;
+
+        let pairs: Vec<(Vec2, Vec2)> = (0..CHECKS)
+            .map(|_| {
+                let from = Vec2::new(
+                    rng.gen_range(-5000.0..5000.0),
+                    rng.gen_range(-5000.0..5000.0),
+                );
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let to = from + Vec2::new(angle.cos(), angle.sin()) * rng.gen_range(0.0..RANGE);
+                (from, to)
+            })
+            .collect();
+
+        // Generate chunks ahead of time.
+        for &(from, to) in &pairs {
+            terrain.sample(from);
+            terrain.sample(to);
+        }
+
+        let start = Instant::now();
+        let visible = pairs
+            .iter()
+            .filter(|(from, to)| terrain.line_of_sight(*from, *to))
+            .count();
+        let elapsed = start.elapsed();
+
+        assert_eq!(visible, CHECKS as usize);
+        println!(
+            "{:?} per line of sight check, up to {}m",
+            elapsed / CHECKS,
+            RANGE
+        );
+    }
+
     #[test]
     fn compress() {
         let mut terrain = Terrain::with_generator(random_generator);
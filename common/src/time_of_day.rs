@@ -0,0 +1,82 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::ticks::Ticks;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+/// Position in the day/night cycle, from 0 (midnight) through 0.5 (noon) to 1 (midnight again).
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimeOfDay(f32);
+
+impl Default for TimeOfDay {
+    /// Mid-morning.
+    fn default() -> Self {
+        Self(0.35)
+    }
+}
+
+impl TimeOfDay {
+    /// Real time, in seconds, that a whole day takes.
+    pub const DAY_SECS: f32 = 20.0 * 60.0;
+    /// Multiplier of visual range at midnight.
+    const NIGHT_VISUAL_FACTOR: f32 = 0.3;
+    /// Multiplier of the range at which active radar can be detected, at midnight.
+    const NIGHT_EMISSION_FACTOR: f32 = 2.0;
+
+    pub fn new(fraction: f32) -> Self {
+        Self(fraction.rem_euclid(1.0))
+    }
+
+    /// Returns the time of day, from 0 to 1.
+    pub fn fraction(self) -> f32 {
+        self.0
+    }
+
+    /// Advances the time of day, wrapping around at midnight.
+    pub fn advance(self, delta: Ticks) -> Self {
+        Self::new(self.0 + delta.to_secs() / Self::DAY_SECS)
+    }
+
+    /// Returns how light it is, from 0 (night) to 1 (day), with short transitions at dawn and
+    /// dusk.
+    pub fn daylight(self) -> f32 {
+        let sun = -(self.0 * TAU).cos();
+        (sun * 3.0 + 0.5).clamp(0.0, 1.0)
+    }
+
+    /// Multiplier of visual range.
+    pub fn visual_factor(self) -> f32 {
+        Self::NIGHT_VISUAL_FACTOR + (1.0 - Self::NIGHT_VISUAL_FACTOR) * self.daylight()
+    }
+
+    /// Multiplier of the range at which active radar emissions can be detected, since they stand
+    /// out more at night.
+    pub fn emission_factor(self) -> f32 {
+        Self::NIGHT_EMISSION_FACTOR + (1.0 - Self::NIGHT_EMISSION_FACTOR) * self.daylight()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ticks::Ticks;
+    use crate::time_of_day::TimeOfDay;
+
+    #[test]
+    fn cycle() {
+        let midnight = TimeOfDay::new(0.0);
+        let noon = TimeOfDay::new(0.5);
+        assert_eq!(midnight.daylight(), 0.0);
+        assert_eq!(noon.daylight(), 1.0);
+        assert!(midnight.visual_factor() < noon.visual_factor());
+        assert!(midnight.emission_factor() > noon.emission_factor());
+        assert_eq!(noon.visual_factor(), 1.0);
+        assert_eq!(noon.emission_factor(), 1.0);
+
+        let mut time = TimeOfDay::new(0.9);
+        for _ in 0..Ticks::from_whole_secs((TimeOfDay::DAY_SECS * 0.2) as u16).0 {
+            time = time.advance(Ticks::ONE);
+        }
+        assert!(time.fraction() < 0.2, "{:?}", time);
+    }
+}
@@ -67,8 +67,7 @@ impl Transform {
             let turn_max = Angle::from_radians(
                 (delta_seconds
                     * match data.kind {
-                        // Longer boats turn slower.
-                        EntityKind::Boat => 0.125 + 20.0 / data.length,
+                        EntityKind::Boat => data.boat_turn_rate(),
                         // Everything else turns slower if moving faster.
                         EntityKind::Aircraft => {
                             2.0 * (1.0 - self.velocity.abs().to_mps() / (1.0 + data.speed.to_mps()))
@@ -119,7 +118,12 @@ impl Transform {
 
     /// do_kinematics updates the position field of a transform based on the direction and velocity fields.
     pub fn do_kinematics(&mut self, delta_seconds: f32) {
-        self.position += self.direction.to_vec() * self.velocity.to_mps() * delta_seconds;
+        self.position += self.velocity_vec() * delta_seconds;
+    }
+
+    /// Returns the velocity as a vector, in meters per second.
+    pub fn velocity_vec(&self) -> Vec2 {
+        self.direction.to_vec() * self.velocity.to_mps()
     }
 
     /// Closest point on self's keel (a line segment from bow to stern) to position.
@@ -137,6 +141,16 @@ impl Transform {
     }
 }
 
+impl DimensionTransform {
+    /// Returns a position relative to the center and direction of self, scaled such that positions
+    /// within the dimensions lie in -1..1 on each axis (x is forward, y is to the side).
+    pub fn to_normalized_local(&self, position: Vec2) -> Vec2 {
+        let normal = self.transform.direction.to_vec();
+        let diff = position - self.transform.position;
+        Vec2::new(diff.dot(normal), diff.dot(normal.perp())) / (self.dimensions * 0.5)
+    }
+}
+
 impl Add for Transform {
     type Output = Self;
 
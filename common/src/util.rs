@@ -3,6 +3,7 @@
 
 use crate::entity::EntityData;
 use common_util::range::map_ranges;
+use glam::Vec2;
 use std::sync::Arc;
 
 /// level_to_score converts a boat level to a score required to upgrade to it.
@@ -101,12 +102,49 @@ pub fn make_mut_slice<T: Clone>(arc: &mut Arc<[T]>) -> &mut [T] {
     }
 }
 
+/// lead_intercept returns where a projectile fired from origin at a constant speed would meet a
+/// target moving at a constant velocity, or the target's current position if it can't.
+pub fn lead_intercept(origin: Vec2, target: Vec2, target_velocity: Vec2, speed: f32) -> Vec2 {
+    let delta = target - origin;
+
+    // Solve |delta + target_velocity * t| = speed * t for the earliest positive t.
+    let a = target_velocity.length_squared() - speed.powi(2);
+    let b = 2.0 * delta.dot(target_velocity);
+    let c = delta.length_squared();
+
+    let t = if a.abs() < 0.001 {
+        // Equally fast, so only one root.
+        -c / b
+    } else {
+        let discriminant = b.powi(2) - 4.0 * a * c;
+        if discriminant < 0.0 {
+            return target;
+        }
+        let sqrt = discriminant.sqrt();
+        let t1 = (-b - sqrt) / (2.0 * a);
+        let t2 = (-b + sqrt) / (2.0 * a);
+        if t1 > 0.0 && (t1 < t2 || t2 <= 0.0) {
+            t1
+        } else {
+            t2
+        }
+    };
+
+    if t.is_finite() && t > 0.0 {
+        target + target_velocity * t
+    } else {
+        target
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::entity::{EntityData, EntityKind, EntityType};
     use crate::util::{
-        kill_score, level_to_score, lose_n_levels, ram_score, respawn_score, score_to_level,
+        kill_score, lead_intercept, level_to_score, lose_n_levels, ram_score, respawn_score,
+        score_to_level,
     };
+    use glam::Vec2;
     use rand::seq::IteratorRandom;
     use rand::{thread_rng, Rng};
 
@@ -191,7 +229,7 @@ mod test {
                 let natural = died == beneficiary || rng.gen_bool(0.5);
                 let mut winnings = boats[died]
                     .0
-                    .loot(boats[died].1, natural)
+                    .loot(&mut rng, boats[died].1, natural)
                     .map(|t| match t {
                         EntityType::Coin => 10,
                         _ => 2,
@@ -236,4 +274,25 @@ mod test {
 
         println!("An average of {}% score remains", percent);
     }
+
+    #[test]
+    fn test_lead_intercept() {
+        let origin = Vec2::ZERO;
+        let target = Vec2::new(100.0, 0.0);
+
+        // Stationary targets need no lead.
+        assert_eq!(lead_intercept(origin, target, Vec2::ZERO, 20.0), target);
+
+        // Crossing targets are met at the same time as the projectile arrives.
+        let velocity = Vec2::new(0.0, 10.0);
+        let intercept = lead_intercept(origin, target, velocity, 20.0);
+        assert!(intercept.y > 0.0);
+        let projectile_time = intercept.distance(origin) / 20.0;
+        let target_time = intercept.distance(target) / 10.0;
+        assert!((projectile_time - target_time).abs() < 0.01);
+
+        // Targets that outrun the projectile can't be intercepted.
+        let velocity = Vec2::new(30.0, 0.0);
+        assert_eq!(lead_intercept(origin, target, velocity, 20.0), target);
+    }
 }
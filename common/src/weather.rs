@@ -0,0 +1,147 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use glam::Vec2;
+use serde::{Deserialize, Serialize};
+
+/// Prevailing wind, in meters per second, which weather cells drift with.
+pub fn wind() -> Vec2 {
+    Vec2::new(7.0, 1.5)
+}
+
+/// Kind of weather within a [`WeatherCell`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum WeatherKind {
+    Fog,
+    Rain,
+    Storm,
+}
+
+impl WeatherKind {
+    /// Multiplier of visual range, at the center of a cell.
+    pub fn visual_factor(self) -> f32 {
+        match self {
+            Self::Fog => 0.35,
+            Self::Rain => 0.7,
+            Self::Storm => 0.5,
+        }
+    }
+
+    /// Multiplier of radar range, at the center of a cell.
+    pub fn radar_factor(self) -> f32 {
+        match self {
+            Self::Fog => 1.0,
+            Self::Rain => 0.8,
+            Self::Storm => 0.6,
+        }
+    }
+}
+
+/// A circular region of weather, drifting across the world.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WeatherCell {
+    pub kind: WeatherKind,
+    pub position: Vec2,
+    /// Meters per second.
+    pub velocity: Vec2,
+    pub radius: f32,
+}
+
+impl WeatherCell {
+    /// Returns how strong the weather is at a position, from 0 (outside) to 1 (center).
+    pub fn intensity(&self, position: Vec2) -> f32 {
+        let distance = self.position.distance(position);
+        (1.0 - distance / self.radius).clamp(0.0, 1.0).sqrt()
+    }
+}
+
+/// The combined effect of all weather at a position.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Weather {
+    pub visual_factor: f32,
+    pub radar_factor: f32,
+    /// Strongest intensity of each [`WeatherKind`], from 0 to 1.
+    pub fog: f32,
+    pub rain: f32,
+    pub storm: f32,
+}
+
+impl Default for Weather {
+    fn default() -> Self {
+        Self {
+            visual_factor: 1.0,
+            radar_factor: 1.0,
+            fog: 0.0,
+            rain: 0.0,
+            storm: 0.0,
+        }
+    }
+}
+
+impl Weather {
+    /// Boats shorter than this are slowed by storms.
+    pub const SMALL_BOAT_LENGTH: f32 = 75.0;
+    /// Maximum speed of small boats in the center of a storm, in meters per second.
+    pub const STORM_MAX_SPEED: f32 = 8.0;
+
+    /// Samples the weather at a position.
+    pub fn sample(cells: &[WeatherCell], position: Vec2) -> Self {
+        let mut ret = Self::default();
+        for cell in cells {
+            let intensity = cell.intensity(position);
+            if intensity == 0.0 {
+                continue;
+            }
+
+            ret.visual_factor *= 1.0 + (cell.kind.visual_factor() - 1.0) * intensity;
+            ret.radar_factor *= 1.0 + (cell.kind.radar_factor() - 1.0) * intensity;
+
+            let kind_intensity = match cell.kind {
+                WeatherKind::Fog => &mut ret.fog,
+                WeatherKind::Rain => &mut ret.rain,
+                WeatherKind::Storm => &mut ret.storm,
+            };
+            *kind_intensity = kind_intensity.max(intensity);
+        }
+        ret
+    }
+
+    /// Returns the maximum speed of a boat of a certain length, if limited by the weather.
+    pub fn max_speed(&self, length: f32) -> Option<f32> {
+        (self.storm > 0.0 && length < Self::SMALL_BOAT_LENGTH).then(|| {
+            // Only binding toward the center of the storm.
+            Self::STORM_MAX_SPEED / self.storm.max(0.05)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::weather::{Weather, WeatherCell, WeatherKind};
+    use glam::Vec2;
+
+    #[test]
+    fn sample() {
+        let cells = [WeatherCell {
+            kind: WeatherKind::Storm,
+            position: Vec2::new(100.0, 0.0),
+            velocity: Vec2::ZERO,
+            radius: 100.0,
+        }];
+
+        assert_eq!(
+            Weather::sample(&cells, Vec2::new(-1.0, 0.0)),
+            Weather::default()
+        );
+
+        let center = Weather::sample(&cells, Vec2::new(100.0, 0.0));
+        assert_eq!(center.storm, 1.0);
+        assert_eq!(center.visual_factor, WeatherKind::Storm.visual_factor());
+        assert_eq!(center.max_speed(50.0), Some(Weather::STORM_MAX_SPEED));
+        assert_eq!(center.max_speed(150.0), None);
+
+        let edge = Weather::sample(&cells, Vec2::new(50.0, 0.0));
+        assert!(edge.storm > 0.0 && edge.storm < 1.0);
+        assert!(edge.visual_factor > center.visual_factor && edge.visual_factor < 1.0);
+    }
+}
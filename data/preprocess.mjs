@@ -116,17 +116,23 @@ for (const entityType of Object.keys(entityDatas)) {
 				entityData.ramDamage = 1.0;
 			}
 
-			// Torpedo resistance.
-			if (typeof entityData.torpedoResistance !== 'number') {
-                switch (entityData.subkind) {
-                    case 'battleship':
-                        entityData.torpedoResistance = 0.4;
-                        break;
-                    case 'cruiser':
-                        entityData.torpedoResistance = 0.2;
-                        break;
-                }
-            }
+			// Damage resistance (fraction of damage resisted, by subkind of what dealt it).
+			if (!entityData.resistance) {
+				entityData.resistance = {};
+			}
+			if (typeof entityData.resistance.torpedo !== 'number') {
+				switch (entityData.subkind) {
+					case 'battleship':
+						entityData.resistance.torpedo = 0.4;
+						break;
+					case 'cruiser':
+						entityData.resistance.torpedo = 0.2;
+						break;
+				}
+			}
+			if (Object.keys(entityData.resistance).length === 0) {
+				delete entityData.resistance;
+			}
 
 			switch (entityData.subkind) {
 				case 'pirate':
@@ -8,8 +8,11 @@ use crate::game_service::GameArenaService;
 use crate::liveboard::LiveboardRepo;
 use crate::player::PlayerRepo;
 use crate::team::TeamRepo;
+use core_protocol::dto::MessageDto;
+use core_protocol::get_unix_time_now;
 use core_protocol::id::ArenaId;
 use server_util::rate_limiter::RateLimiterProps;
+use std::sync::Arc;
 
 /// Things that go along with every instance of a [`GameArenaService`].
 pub struct Context<G: GameArenaService> {
@@ -40,4 +43,23 @@ impl<G: GameArenaService> Context<G> {
             liveboard: LiveboardRepo::new(),
         }
     }
+
+    /// Sends a chat message to all players, on behalf of [`GameArenaService::authority_alias`].
+    pub fn broadcast_authority_message(&mut self, text: String) {
+        let alias = G::authority_alias();
+        self.chat.log_chat(alias, &text, false, "ok");
+
+        let message = MessageDto {
+            alias,
+            date_sent: get_unix_time_now(),
+            player_id: None,
+            team_captain: false,
+            team_name: None,
+            text,
+            whisper: false,
+        };
+
+        self.chat
+            .broadcast_message(Arc::new(message), &mut self.players);
+    }
 }
@@ -433,6 +433,12 @@ impl<G: GameArenaService> PlayerData<G> {
         self.team.team_id()
     }
 
+    /// Sets the player's [`TeamId`] for players that aren't in an arena (e.g. when replaying a
+    /// recording). They must be made solo again before being dropped.
+    pub fn set_team_id_outside_arena(&mut self, team_id: Option<TeamId>) {
+        self.team.set_team_id(team_id);
+    }
+
     /// Gets any invitation accepted by the player (always [`None`] for bots).
     pub fn invitation_accepted(&self) -> Option<&InvitationDto> {
         self.client()
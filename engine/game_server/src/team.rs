@@ -143,6 +143,12 @@ impl PlayerTeamData {
             PlayerTeamStatus::Solo { .. } => None,
         }
     }
+
+    /// Sets [`TeamId`] without going through [`TeamRepo`].
+    pub(crate) fn set_team_id(&mut self, team_id: Option<TeamId>) {
+        self.status = team_id.map_or_else(PlayerTeamStatus::solo, PlayerTeamStatus::teamed);
+        self.previous_team_id = team_id;
+    }
 }
 
 impl ClientTeamData {
@@ -116,6 +116,8 @@ export const resolution = jsSettingsStore('resolution', 1.0, 0.25, 1.0);
 
 export const waveQuality = rustSettingStore( 'waveQuality');
 export const animations = rustSettingStore( 'animations');
+export const contactMemory = rustSettingStore( 'contactMemory');
+export const torpedoSpread = rustSettingStore( 'torpedoSpread');
 export const volume = rustSettingStore( 'volume');
 export const antialias = rustSettingStore( 'antialias');
 
@@ -9,7 +9,7 @@ use proc_macro::TokenStream;
 use proc_macro2::Span;
 use quote::quote;
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::fs;
@@ -37,15 +37,41 @@ pub fn entity_type(item: TokenStream) -> TokenStream {
     #[derive(Deserialize)]
     struct Subset {
         kind: String,
+        subkind: String,
         length: f32,
         width: f32,
         #[serde(default)]
         level: u8,
+        #[serde(default)]
+        resistance: HashMap<String, f32>,
     }
 
     let entity_data: HashMap<String, Subset> =
         serde_json::from_str(&json).expect("unable to parse entity json");
 
+    // Resistances must be to something that exists, and can't be more than complete immunity.
+    // Ramming by any boat is resisted with the dedicated "ram" key.
+    let subkinds: HashSet<&str> = entity_data
+        .values()
+        .map(|s| s.subkind.as_str())
+        .chain(std::iter::once("ram"))
+        .collect();
+    for (name, subset) in &entity_data {
+        for (subkind, resistance) in &subset.resistance {
+            if !subkinds.contains(subkind.as_str()) {
+                let msg = format!("{} has resistance to unknown subkind {}", name, subkind);
+                return quote! { compile_error!(#msg) }.into();
+            }
+            if !(0.0..=1.0).contains(resistance) {
+                let msg = format!(
+                    "{} has resistance {} to {}, which is not between 0 and 1",
+                    name, resistance, subkind
+                );
+                return quote! { compile_error!(#msg) }.into();
+            }
+        }
+    }
+
     let mut max_radius = 0f32;
     let mut max_boat_level = 0u8;
 
@@ -0,0 +1,325 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::server::Server;
+use crate::world::World;
+use common::ticks::Ticks;
+use common_util::range::gen_radius;
+use core_protocol::id::{PlayerId, TeamId};
+use game_server::player::PlayerTuple;
+use glam::Vec2;
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Round based mode, in which the border shrinks on a schedule toward a random center, sunken boats
+/// may not respawn, and the last surviving player or team wins.
+pub struct BattleRoyale {
+    phase: RoundPhase,
+    /// Chat messages that have yet to be sent by the server.
+    announcements: Vec<String>,
+}
+
+#[derive(Copy, Clone, Debug)]
+enum RoundPhase {
+    /// Players may spawn, until the countdown reaches zero.
+    Lobby { remaining: Ticks },
+    /// Border is shrinking, and no one may spawn.
+    Active {
+        /// Where the border will converge.
+        center: Vec2,
+        /// Radius of the border when the round started.
+        initial_radius: f32,
+        elapsed: Ticks,
+    },
+}
+
+/// One step of the border shrinking schedule.
+struct Stage {
+    /// How long the border holds still before shrinking.
+    hold: Ticks,
+    /// How long the border takes to shrink.
+    shrink: Ticks,
+    /// Radius of the border after shrinking, relative to the initial radius.
+    fraction: f32,
+}
+
+/// Players on the same team survive (and win) together.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+enum Side {
+    Team(TeamId),
+    Player(PlayerId),
+}
+
+impl BattleRoyale {
+    /// How long players have to spawn before each round.
+    const LOBBY: Ticks = Ticks::from_whole_secs(60);
+    /// Fewest sides required to start a round.
+    const MIN_SIDES: usize = 2;
+    /// Awarded to each surviving member of the winning side, and therefore reflected on the
+    /// leaderboard.
+    const WIN_SCORE: u32 = 500;
+    /// The final stage must shrink the border to nothing, so that rounds always end.
+    const SCHEDULE: [Stage; 4] = [
+        Stage {
+            hold: Ticks::from_whole_secs(60),
+            shrink: Ticks::from_whole_secs(90),
+            fraction: 0.6,
+        },
+        Stage {
+            hold: Ticks::from_whole_secs(45),
+            shrink: Ticks::from_whole_secs(60),
+            fraction: 0.3,
+        },
+        Stage {
+            hold: Ticks::from_whole_secs(30),
+            shrink: Ticks::from_whole_secs(45),
+            fraction: 0.1,
+        },
+        Stage {
+            hold: Ticks::from_whole_secs(60),
+            shrink: Ticks::from_whole_secs(60),
+            fraction: 0.0,
+        },
+    ];
+
+    pub fn new() -> Self {
+        let mut ret = Self {
+            phase: RoundPhase::Lobby {
+                remaining: Self::LOBBY,
+            },
+            announcements: Vec::new(),
+        };
+        ret.announce_lobby();
+        ret
+    }
+
+    /// Returns true if players may currently spawn.
+    pub fn can_spawn(&self) -> bool {
+        matches!(self.phase, RoundPhase::Lobby { .. })
+    }
+
+    /// Returns chat messages that should be sent to all players, clearing them in the process.
+    pub fn take_announcements(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.announcements)
+    }
+
+    fn announce_lobby(&mut self) {
+        self.announcements.push(format!(
+            "Next battle royale round starts in {} seconds.",
+            Self::LOBBY.to_secs() as u32
+        ));
+    }
+
+    /// Returns the radius of the border, relative to the initial radius, some time into a round.
+    fn border_fraction(mut elapsed: Ticks) -> f32 {
+        let mut fraction = 1.0;
+        for stage in &Self::SCHEDULE {
+            if elapsed < stage.hold {
+                return fraction;
+            }
+            elapsed -= stage.hold;
+            if elapsed < stage.shrink {
+                let progress = elapsed.to_secs() / stage.shrink.to_secs();
+                return fraction + (stage.fraction - fraction) * progress;
+            }
+            elapsed -= stage.shrink;
+            fraction = stage.fraction;
+        }
+        fraction
+    }
+}
+
+impl World {
+    /// Advances the battle royale round, if enabled. Returns true if the border is following the
+    /// round's schedule, as opposed to the number of boats.
+    pub fn update_battle_royale(&mut self, delta: Ticks) -> bool {
+        if self.battle_royale.is_none() {
+            return false;
+        }
+
+        let sides = self.surviving_sides();
+        let battle_royale = self.battle_royale.as_mut().unwrap();
+
+        match battle_royale.phase {
+            RoundPhase::Lobby { remaining } => {
+                let remaining = remaining.saturating_sub(delta);
+                battle_royale.phase = if remaining != Ticks::ZERO {
+                    RoundPhase::Lobby { remaining }
+                } else if sides.len() < BattleRoyale::MIN_SIDES {
+                    battle_royale
+                        .announcements
+                        .push(String::from("Not enough players to start the round."));
+                    battle_royale.announce_lobby();
+                    RoundPhase::Lobby {
+                        remaining: BattleRoyale::LOBBY,
+                    }
+                } else {
+                    battle_royale.announcements.push(String::from(
+                        "Round started! The border is shrinking, and sunken boats may not respawn.",
+                    ));
+                    RoundPhase::Active {
+                        center: gen_radius(&mut self.rng, self.radius * 0.5),
+                        initial_radius: self.radius,
+                        elapsed: Ticks::ZERO,
+                    }
+                };
+                false
+            }
+            RoundPhase::Active {
+                center,
+                initial_radius,
+                elapsed,
+            } => {
+                if sides.len() > 1 {
+                    let elapsed = elapsed.saturating_add(delta);
+                    let fraction = BattleRoyale::border_fraction(elapsed);
+
+                    // The border converges on the center. Since the center is closer to the origin
+                    // than the initial radius, the border never grows past where it was before.
+                    self.radius = initial_radius * fraction;
+                    self.center = center * (1.0 - fraction);
+
+                    battle_royale.phase = RoundPhase::Active {
+                        center,
+                        initial_radius,
+                        elapsed,
+                    };
+                    return true;
+                }
+
+                let winners = sides.into_values().next().unwrap_or_default();
+                let mut aliases = Vec::with_capacity(winners.len());
+                for player_tuple in &winners {
+                    let mut player = player_tuple.borrow_player_mut();
+                    player.score += BattleRoyale::WIN_SCORE;
+                    aliases.push(player.alias().to_string());
+                }
+
+                battle_royale.announcements.push(if aliases.is_empty() {
+                    String::from("Round over, there were no survivors.")
+                } else {
+                    format!("Round over, {} won!", aliases.join(", "))
+                });
+                battle_royale.announce_lobby();
+                battle_royale.phase = RoundPhase::Lobby {
+                    remaining: BattleRoyale::LOBBY,
+                };
+
+                // Survivors are within the initial border, so restore it.
+                self.radius = initial_radius;
+                self.center = Vec2::ZERO;
+                false
+            }
+        }
+    }
+
+    /// Groups players with boats by team (or by player, if not on a team).
+    fn surviving_sides(&self) -> BTreeMap<Side, Vec<Arc<PlayerTuple<Server>>>> {
+        let survivors: Vec<Arc<PlayerTuple<Server>>> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, entity)| entity.is_boat())
+            .map(|(_, entity)| Arc::clone(entity.player.as_ref().unwrap()))
+            .collect();
+
+        let mut sides = BTreeMap::new();
+        for player_tuple in survivors {
+            let player = player_tuple.borrow_player();
+            let side = player
+                .team_id()
+                .map(Side::Team)
+                .unwrap_or(Side::Player(player.player_id));
+            drop(player);
+            sides
+                .entry(side)
+                .or_insert_with(Vec::new)
+                .push(player_tuple);
+        }
+        sides
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::battle_royale::BattleRoyale;
+    use crate::bot::Bot;
+    use crate::protocol::AsCommandTrait;
+    use crate::world::World;
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::entity::EntityType;
+    use common::protocol::Command;
+    use common::ticks::Ticks;
+    use game_server::game_service::BotAction;
+    use glam::Vec2;
+
+    #[test]
+    fn border_fraction() {
+        let mut elapsed = Ticks::ZERO;
+        let mut previous = BattleRoyale::border_fraction(elapsed);
+        assert_eq!(previous, 1.0);
+
+        while elapsed < Ticks::from_whole_secs(1000) {
+            elapsed = elapsed.next();
+            let fraction = BattleRoyale::border_fraction(elapsed);
+            assert!(fraction <= previous);
+            previous = fraction;
+        }
+
+        assert_eq!(previous, 0.0);
+    }
+
+    #[test]
+    fn round() {
+        crate::noise::init();
+        unsafe { EntityType::init() };
+
+        let mut world = World::with_seed(1500.0, 7);
+        world.battle_royale = Some(BattleRoyale::new());
+
+        let mut bots: Vec<_> = (0..4)
+            .map(|i| {
+                let player = test_bot(i);
+                Server::join(&mut world, &player);
+                (player, Bot::with_seed(i as u64))
+            })
+            .collect();
+
+        let mut started = false;
+        let mut announcements = Vec::new();
+
+        for _ in 0..Ticks::from_whole_secs(1000).0 {
+            for (player, bot) in bots.iter_mut() {
+                let player_id = player.borrow_player().player_id;
+                if let BotAction::Some(command) = bot.update(
+                    world.get_player_complete(player),
+                    player_id,
+                    world.players_can_spawn(),
+                ) {
+                    // Bots wait for the round to end instead of trying to spawn.
+                    assert!(world.players_can_spawn() || !matches!(command, Command::Spawn(_)));
+                    let _ = command.as_command().apply(&mut world, player);
+                }
+            }
+            world.update(Ticks::ONE);
+
+            let battle_royale = world.battle_royale.as_mut().unwrap();
+            announcements.extend(battle_royale.take_announcements());
+            if !battle_royale.can_spawn() {
+                started = true;
+            } else if started {
+                break;
+            }
+        }
+
+        assert!(started, "{:?}", announcements);
+        assert!(
+            announcements.iter().any(|a| a.starts_with("Round over")),
+            "{:?}",
+            announcements
+        );
+        assert_eq!(world.center, Vec2::ZERO);
+    }
+}
@@ -18,9 +18,9 @@ use core_protocol::id::PlayerId;
 use game_server::game_service::{BotAction, GameArenaService};
 use game_server::player::{PlayerRepo, PlayerTuple};
 use glam::Vec2;
-use rand::rngs::ThreadRng;
+use rand::rngs::StdRng;
 use rand::seq::IteratorRandom;
-use rand::{thread_rng, Rng};
+use rand::{thread_rng, Rng, SeedableRng};
 use std::sync::Arc;
 
 /// Bot implements a ship-controlling AI that is, in many ways, equivalent to a player.
@@ -37,13 +37,22 @@ pub struct Bot {
     spawned_at_least_once: bool,
     /// The value of submerge previously sent.
     was_submerging: bool,
+    /// Source of all of the bot's randomness, so a seeded bot behaves deterministically.
+    rng: StdRng,
 }
 
 impl Default for Bot {
     fn default() -> Self {
-        let mut rng = thread_rng();
+        Self::with_seed(thread_rng().gen())
+    }
+}
 
-        fn random_level(rng: &mut ThreadRng) -> u8 {
+impl Bot {
+    /// Creates a bot whose personality and decisions are determined by a seed.
+    pub fn with_seed(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+
+        fn random_level(rng: &mut StdRng) -> u8 {
             rng.gen_range(1..=EntityData::MAX_BOAT_LEVEL)
         }
 
@@ -56,31 +65,37 @@ impl Default for Bot {
             level_ambition: random_level(&mut rng).min(random_level(&mut rng)),
             spawned_at_least_once: false,
             was_submerging: false,
+            rng,
         }
     }
-}
 
-impl Bot {
     /// This arbitrary value controls how chill the bots are. If too high, bots are trigger-happy
     /// maniacs, and the waters get filled with stray torpedoes.
     const MAX_AGGRESSION: f32 = 0.1;
 
     /// Returns true if there is land or border at the given position.
-    fn is_land_or_border(pos: Vec2, terrain: &Terrain, world_radius: f32) -> bool {
-        if pos.length_squared() > world_radius.powi(2) {
+    fn is_land_or_border(
+        pos: Vec2,
+        terrain: &Terrain,
+        world_center: Vec2,
+        world_radius: f32,
+    ) -> bool {
+        if pos.distance_squared(world_center) > world_radius.powi(2) {
             return true;
         }
 
         terrain.sample(pos).unwrap_or(Altitude::MIN) >= terrain::SAND_LEVEL
     }
 
-    /// update processes a complete update and returns some command (or None to quit).
-    fn update<'a, U: 'a + CompleteTrait<'a>>(
+    /// update processes a complete update and returns some command (or None to quit). Doesn't
+    /// try to spawn unless can_spawn (see `World::can_spawn`).
+    pub(crate) fn update<'a, U: 'a + CompleteTrait<'a>>(
         &mut self,
         mut update: U,
         player_id: PlayerId,
+        can_spawn: bool,
     ) -> BotAction<Command> {
-        let mut rng = thread_rng();
+        let mut rng = &mut self.rng;
 
         let mut contacts = update.contacts();
         let terrain = update.terrain();
@@ -121,6 +136,7 @@ impl Bot {
                 if Self::is_land_or_border(
                     boat.transform().position + delta_position,
                     terrain,
+                    update.world_center(),
                     update.world_radius(),
                 ) {
                     repel(&mut movement, delta_position, 0.5 * data.length.powi(2));
@@ -220,7 +236,9 @@ impl Bot {
                     let relevant = match enemy_data.kind {
                         EntityKind::Aircraft | EntityKind::Weapon => {
                             if enemy.altitude().is_airborne() {
-                                matches!(armament_entity_data.sub_kind, EntitySubKind::Sam)
+                                armament_entity_data.sub_kind == EntitySubKind::Sam
+                                    || (enemy_data.sub_kind == EntitySubKind::Missile
+                                        && armament_entity_data.sub_kind == EntitySubKind::Chaff)
                             } else if enemy_data.sub_kind == EntitySubKind::Torpedo
                                 && enemy_data.sensors.sonar.range > 0.0
                             {
@@ -319,24 +337,38 @@ impl Bot {
                 false
             };
 
+            // Steer upstream of the desired course, to compensate for drifting with the current.
+            let velocity_target = data.speed * 0.8;
+            let drift = terrain.current(boat.transform().position) * data.current_factor();
+            let heading = movement.normalize_or_zero() * velocity_target.to_mps() - drift;
+
+            // Closures can't borrow self while rng is borrowed.
+            let aim_bias = self.aim_bias;
+            let aggression = self.aggression as f64;
+
             let mut ret = Command::Control(Control {
                 guidance: Some(Guidance {
-                    direction_target: Angle::from(movement) + self.steer_bias,
-                    velocity_target: data.speed * 0.8,
+                    direction_target: Angle::from(heading) + self.steer_bias,
+                    velocity_target,
                 }),
-                submerge: self.was_submerging,
-                aim_target: best_firing_solution.map(|solution| solution.1 + self.aim_bias),
+                altitude_target: if self.was_submerging {
+                    Altitude::MIN
+                } else {
+                    Altitude::ZERO
+                },
+                aim_target: best_firing_solution.map(|solution| solution.1 + aim_bias),
                 active: health_percent >= 0.5,
                 fire: best_firing_solution
-                    .filter(|_| rng.gen_bool(self.aggression as f64))
+                    .filter(|_| rng.gen_bool(aggression))
                     .map(|sol| Fire {
                         armament_index: sol.0,
                     }),
+                salvo: None,
                 pay: None,
                 hint: None,
             });
 
-            if rng.gen_bool(self.aggression as f64) && data.level < self.level_ambition {
+            if rng.gen_bool(aggression) && data.level < self.level_ambition {
                 // Upgrade, if possible.
                 if let Some(entity_type) = boat_type
                     .upgrade_options(update.score(), true)
@@ -350,6 +382,9 @@ impl Bot {
         } else if self.spawned_at_least_once && rng.gen_bool(1.0 / 3.0) {
             // Rage quit.
             BotAction::Quit
+        } else if !can_spawn {
+            // Wait for the battle royale round to end.
+            BotAction::None
         } else {
             BotAction::Some(Command::Spawn(Spawn {
                 entity_type: EntityType::spawn_options(true)
@@ -361,22 +396,26 @@ impl Bot {
 }
 
 impl game_server::game_service::Bot<Server> for Bot {
-    type Input<'a> = CompleteRef<'a, impl Iterator<Item = ContactRef<'a>>>;
+    /// The complete update, and whether spawning is possible.
+    type Input<'a> = (CompleteRef<'a, impl Iterator<Item = ContactRef<'a>>>, bool);
 
     fn get_input<'a>(
         server: &'a Server,
         player: &'a Arc<PlayerTuple<Server>>,
         _players: &'a PlayerRepo<Server>,
     ) -> Self::Input<'a> {
-        server.world.get_player_complete(player)
+        (
+            server.world.get_player_complete(player),
+            server.world.players_can_spawn(),
+        )
     }
 
     fn update(
         &mut self,
-        update: Self::Input<'_>,
+        (update, can_spawn): Self::Input<'_>,
         player_id: PlayerId,
         _players: &PlayerRepo<Server>,
     ) -> BotAction<<Server as GameArenaService>::GameRequest> {
-        self.update(update, player_id)
+        self.update(update, player_id, can_spawn)
     }
 }
@@ -4,18 +4,20 @@
 use crate::contact_ref::ContactRef;
 use crate::player::Status;
 use crate::server::Server;
+use crate::territory::Territory;
 use crate::world::World;
 use atomic_refcell::AtomicRef;
 use common::complete::CompleteTrait;
 use common::contact::ContactTrait;
 use common::death_reason::DeathReason;
-use common::protocol::Update;
+use common::protocol::{DataLinkContact, RouteProgress, SonarContact, Update};
 use common::terrain;
 use common::terrain::{ChunkSet, Terrain};
 use common::ticks::{Ticks, TicksRepr};
 use common::velocity::Velocity;
 use game_server::player::PlayerData;
 use glam::Vec2;
+use std::collections::HashSet;
 use std::ops::RangeInclusive;
 
 /// A "Complete" server to client update that references world data to avoid additional allocation.
@@ -85,34 +87,139 @@ impl<'a, I: Iterator<Item = ContactRef<'a>>> CompleteRef<'a, I> {
 
         *loaded_chunks = new_loaded_chunks;
 
-        Update {
-            contacts: self
-                .contacts
-                .unwrap()
-                .filter_map(|contact| {
-                    let modulus = if let Some(entity_type) = contact.entity_type() {
-                        let range: RangeInclusive<Ticks> = entity_type.data().kind.keep_alive();
-
-                        if contact.transform().velocity.abs() > Velocity::from_mps(1.0) {
-                            // Send more often if moving.
-                            *range.start()
-                        } else {
-                            *range.end()
-                        }
+        let player_entity = if let Status::Alive { entity_index, .. } = &self.player.data.status {
+            Some(&self.world.entities[*entity_index])
+        } else {
+            None
+        };
+
+        let route = player_entity.and_then(|entity| {
+            let extension = entity.extension();
+            extension.route.as_ref().map(|route| RouteProgress {
+                waypoints: route.waypoints.clone(),
+                blocked: extension.route_blocked,
+            })
+        });
+
+        let endurance = player_entity.and_then(|entity| {
+            (entity.data().endurance != Ticks::ZERO).then(|| entity.extension().endurance())
+        });
+
+        // Only sinkings on screen.
+        let half_camera_dims = self.camera_dims * 0.5;
+        let sunk = self
+            .world
+            .sunk
+            .iter()
+            .filter(|(_, position)| {
+                let diff = (*position - self.camera_pos).abs();
+                diff.x <= half_camera_dims.x && diff.y <= half_camera_dims.y
+            })
+            .map(|(id, _)| *id)
+            .collect();
+
+        let component_damage =
+            player_entity.and_then(|entity| entity.extension().component_damage(entity.data()));
+
+        let lock = player_entity
+            .and_then(|entity| entity.extension().lock.as_ref().map(|lock| lock.target));
+
+        let mut sonar_contacts = Vec::new();
+        let mut data_link = Vec::new();
+        // Contacts whose positions are known exactly.
+        let mut exact_contacts = HashSet::new();
+        let camera_pos = self.camera_pos;
+
+        let contacts = self
+            .contacts
+            .unwrap()
+            .filter_map(|contact| {
+                if let Some((position, radius)) = contact.estimate() {
+                    // Sent every update, so not subject to keep alive.
+                    sonar_contacts.push(SonarContact {
+                        bearing: contact.bearing_from(camera_pos),
+                        position,
+                        radius,
+                    });
+                    return None;
+                }
+
+                exact_contacts.insert(contact.id());
+
+                if let Some(spotter) = contact.spotter() {
+                    // Sent every update, so the client knows when it no longer applies.
+                    data_link.push(DataLinkContact {
+                        entity_id: contact.id(),
+                        spotter,
+                    });
+                }
+
+                let modulus = if let Some(entity_type) = contact.entity_type() {
+                    let range: RangeInclusive<Ticks> = entity_type.data().kind.keep_alive();
+
+                    if contact.transform().velocity.abs() > Velocity::from_mps(1.0) {
+                        // Send more often if moving.
+                        *range.start()
                     } else {
-                        Ticks::from_repr(5)
-                    };
-
-                    let send = counter
-                        .wrapping_add(Ticks::from_repr(contact.id().get() as TicksRepr))
-                        % (modulus + Ticks::ONE)
-                        == Ticks::ZERO;
-                    send.then(|| contact.into_contact())
-                })
-                .collect(),
+                        *range.end()
+                    }
+                } else {
+                    Ticks::from_repr(5)
+                };
+
+                let send = counter.wrapping_add(Ticks::from_repr(contact.id().get() as TicksRepr))
+                    % (modulus + Ticks::ONE)
+                    == Ticks::ZERO;
+                send.then(|| contact.into_contact())
+            })
+            .collect();
+
+        // Only bursts on screen, fired by boats that are contacts (otherwise, they would give
+        // away the boat's position).
+        let ciws_bursts = self
+            .world
+            .ciws_bursts
+            .iter()
+            .filter(|(boat_id, burst)| {
+                let diff = (burst.position - camera_pos).abs();
+                diff.x <= half_camera_dims.x
+                    && diff.y <= half_camera_dims.y
+                    && exact_contacts.contains(boat_id)
+            })
+            .map(|(_, burst)| burst.clone())
+            .collect();
+
+        Update {
+            contacts,
+            sonar_contacts,
+            data_link,
+            route,
+            endurance,
+            component_damage,
+            ciws_bursts,
+            sunk,
+            lock,
             death_reason,
             score: self.player.score,
             world_radius: self.world.radius,
+            world_center: self.world.center,
+            capture_points: self
+                .world
+                .territory
+                .as_ref()
+                .map(Territory::capture_points)
+                .unwrap_or_default(),
+            weather: self
+                .world
+                .weather
+                .iter()
+                .filter(|cell| {
+                    cell.position.distance(self.camera_pos)
+                        < cell.radius + self.camera_dims.length()
+                })
+                .cloned()
+                .collect(),
+            time_of_day: self.world.time_of_day,
             terrain,
         }
     }
@@ -148,6 +255,11 @@ impl<'a, I: Iterator<Item = ContactRef<'a>>> CompleteTrait<'a> for CompleteRef<'
         self.world.radius
     }
 
+    #[inline]
+    fn world_center(&self) -> Vec2 {
+        self.world.center
+    }
+
     #[inline]
     fn terrain(&self) -> &Terrain {
         // TODO limit visibility of terrain.
@@ -13,14 +13,28 @@ use common::entity::EntityType;
 use common::guidance::Guidance;
 use common::ticks::Ticks;
 use common::transform::Transform;
+use common::velocity::Velocity;
 use core_protocol::id::PlayerId;
+use glam::Vec2;
 use std::sync::Arc;
 
+static GUIDANCE_ZERO: Guidance = Guidance {
+    direction_target: Angle::ZERO,
+    velocity_target: Velocity::ZERO,
+};
+
 /// A contact that references world data to avoid additional allocation.
 pub struct ContactRef<'a> {
     entity: &'a Entity,
     has_type: bool,
     reloads: Option<BitArray<ReloadsStorage>>,
+    /// If only the approximate position is known, a transform at that position, and the radius
+    /// in which the actual position lies.
+    estimate: Option<(Transform, f32)>,
+    /// If the contact was only detected by a teammate, who spotted it.
+    spotter: Option<PlayerId>,
+    /// Turret angles sent in place of the actual ones, which aren't shared by teammates.
+    default_turrets: Option<Arc<[Angle]>>,
 }
 
 impl<'a> ContactRef<'a> {
@@ -43,17 +57,67 @@ impl<'a> ContactRef<'a> {
             entity,
             has_type,
             reloads,
+            estimate: None,
+            spotter: None,
+            default_turrets: None,
         }
     }
 
+    /// Marks the contact as spotted by a teammate, which hides the actual turret angles and
+    /// reloads.
+    pub fn with_spotter(mut self, spotter: PlayerId) -> Self {
+        self.reloads = None;
+        if self.turrets_known() {
+            let data = self.entity.data();
+            self.default_turrets = Some(Arc::from_iter(data.turrets.iter().map(|t| t.angle)));
+        }
+        self.spotter = Some(spotter);
+        self
+    }
+
+    /// Returns the teammate that spotted the contact, if the player didn't detect it themselves.
+    pub fn spotter(&self) -> Option<PlayerId> {
+        self.spotter
+    }
+
+    /// Hides everything but an approximate position, which is within a radius of the actual
+    /// position.
+    pub fn with_estimate(mut self, position: Vec2, radius: f32) -> Self {
+        debug_assert!(!self.has_type);
+        self.estimate = Some((
+            Transform {
+                position,
+                ..Transform::default()
+            },
+            radius,
+        ));
+        self
+    }
+
+    /// Returns the approximate position and radius of uncertainty, if the actual position isn't
+    /// known.
+    pub fn estimate(&self) -> Option<(Vec2, f32)> {
+        self.estimate
+            .as_ref()
+            .map(|(transform, radius)| (transform.position, *radius))
+    }
+
+    /// Returns the direction from an observer to the actual position, which is known even if only
+    /// an estimate of the position is.
+    pub fn bearing_from(&self, observer: Vec2) -> Angle {
+        Angle::from(self.entity.transform.position - observer)
+    }
+
     /// Converts into a non-ref `Contact`.
     pub fn into_contact(self) -> Contact {
         Contact::new(
             self.altitude(),
             self.damage(),
             self.entity_type(),
+            self.flooding(),
             *self.guidance(),
             self.id(),
+            self.on_fire(),
             self.player_id(),
             self.reloads,
             *self.transform(),
@@ -63,7 +127,11 @@ impl<'a> ContactRef<'a> {
 
     fn turrets_arc(&self) -> Option<&Arc<[Angle]>> {
         if self.turrets_known() {
-            Some(&self.entity.extension().turrets)
+            Some(
+                self.default_turrets
+                    .as_ref()
+                    .unwrap_or(&self.entity.extension().turrets),
+            )
         } else {
             None
         }
@@ -95,9 +163,18 @@ impl<'a> ContactTrait for ContactRef<'a> {
         }
     }
 
+    #[inline]
+    fn flooding(&self) -> bool {
+        self.turrets_known() && self.entity.extension().flooding.is_some()
+    }
+
     #[inline]
     fn guidance(&self) -> &Guidance {
-        &self.entity.guidance
+        if self.estimate.is_some() {
+            &GUIDANCE_ZERO
+        } else {
+            &self.entity.guidance
+        }
     }
 
     #[inline]
@@ -105,8 +182,16 @@ impl<'a> ContactTrait for ContactRef<'a> {
         self.entity.id
     }
 
+    #[inline]
+    fn on_fire(&self) -> bool {
+        self.turrets_known() && self.entity.extension().fire.is_some()
+    }
+
     #[inline]
     fn player_id(&self) -> Option<PlayerId> {
+        if self.estimate.is_some() {
+            return None;
+        }
         self.entity
             .player
             .as_ref()
@@ -128,7 +213,9 @@ impl<'a> ContactTrait for ContactRef<'a> {
 
     #[inline]
     fn transform(&self) -> &Transform {
-        &self.entity.transform
+        self.estimate
+            .as_ref()
+            .map_or(&self.entity.transform, |(transform, _)| transform)
     }
 
     #[inline]
@@ -120,7 +120,7 @@ impl TryFrom<Vec2> for SectorId {
     }
 }
 
-#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Ord, PartialOrd, Eq, PartialEq, Hash)]
 pub struct EntityIndex(SectorId, u16);
 
 impl EntityIndex {
@@ -4,7 +4,7 @@
 use crate::arena::Arena;
 use crate::collision::{radius_collision, sat_collision};
 use crate::entities::*;
-use crate::entity_extension::EntityExtension;
+use crate::entity_extension::{EntityExtension, Hazard};
 use crate::player::*;
 use crate::server::Server;
 use atomic_refcell::{AtomicRef, AtomicRefMut};
@@ -13,16 +13,72 @@ use common::angle::Angle;
 use common::death_reason::DeathReason;
 use common::entity::*;
 use common::guidance::Guidance;
+use common::protocol::SquadronOrder;
 use common::terrain::*;
 use common::ticks::{Ticks, TicksRepr};
 use common::transform::{DimensionTransform, Transform};
-use common::util::hash_u32_to_f32;
+use common::util::{hash_u32_to_f32, lead_intercept};
+use common::velocity::Velocity;
+use common_util::range::map_ranges;
 use game_server::player::{PlayerData, PlayerTuple};
 use glam::Vec2;
+use rand::Rng;
+use std::collections::HashMap;
 use std::ptr;
 use std::sync::Arc;
 use std::time::Instant;
 
+/// What weapons and aircraft need to know about the boat that launched them, which is gathered
+/// before entities are mutated in parallel.
+pub struct Launcher {
+    pub transform: Transform,
+    /// How fast (in radians per second) the boat is turning.
+    pub turn_rate: f32,
+    /// Landing pads waiting for their aircraft to return.
+    pub pads: Vec<(EntityType, Transform)>,
+    /// Last known transform of the contact the boat is locked on to, if any.
+    pub lock: Option<Transform>,
+}
+
+impl Launcher {
+    pub fn new(boat: &Entity, delta_seconds: f32) -> Self {
+        let data = boat.data();
+        let extension = boat.extension();
+        let pads = data
+            .armaments
+            .iter()
+            .enumerate()
+            .filter(|(i, armament)| {
+                armament.entity_type.data().kind == EntityKind::Aircraft
+                    && extension.reloads[*i] == Ticks::MAX
+            })
+            .map(|(i, armament)| {
+                (
+                    armament.entity_type,
+                    boat.transform + data.armament_transform(&extension.turrets, i),
+                )
+            })
+            .collect();
+
+        // The boat will turn towards its target direction as fast as it can, up to a point.
+        let turn = (boat.guidance.direction_target - boat.transform.direction)
+            .abs()
+            .to_radians();
+        let turn_rate = if delta_seconds > 0.0 {
+            (turn / delta_seconds).min(data.boat_turn_rate())
+        } else {
+            0.0
+        };
+
+        Self {
+            transform: boat.transform,
+            turn_rate,
+            pads,
+            lock: extension.lock.as_ref().map(|lock| lock.transform),
+        }
+    }
+}
+
 /// A game object, such as a boat, weapon, or obstacle.
 /// Lots of effort is made to keep this only 32 bytes, to improve cache locality.
 #[derive(Debug)]
@@ -47,6 +103,33 @@ pub fn unset_entity_id() -> EntityId {
 }
 
 impl Entity {
+    /// How long fires burn.
+    const FIRE_DURATION: Ticks = Ticks::from_whole_secs(10);
+    /// Fraction of max health that fire deals per second.
+    const FIRE_RATE: f32 = 0.01;
+    /// How long it takes to pump out flooding.
+    const FLOODING_DURATION: Ticks = Ticks::from_whole_secs(15);
+    /// Fraction of max health that flooding deals per second.
+    const FLOODING_RATE: f32 = 0.01;
+    /// How much flooding increases draft.
+    const FLOODING_DRAFT: Altitude = Altitude(1);
+    /// Fraction of max speed that flooded boats can attain.
+    pub const FLOODING_SPEED: f32 = 0.7;
+    /// How far a wire-guided weapon can get from its launcher before the wire breaks.
+    const WIRE_LENGTH: f32 = 1200.0;
+    /// Speed (in meters per second) above which a launcher breaks its wires.
+    const WIRE_MAX_LAUNCHER_SPEED: f32 = 8.0;
+    /// Turn rate (in degrees per second) above which a launcher breaks its wires.
+    const WIRE_MAX_LAUNCHER_TURN_RATE: f32 = 10.0;
+    /// How fast (in degrees per second) a wire-guided weapon can be steered.
+    const WIRE_TURN_RATE: f32 = 20.0;
+    /// Fraction of fuel at which aircraft return to their launcher.
+    const BINGO_FUEL: f32 = 0.3;
+    /// Radius (in meters) of the circle flown by aircraft on combat air patrol.
+    const PATROL_RADIUS: f32 = 400.0;
+    /// Radius (in meters) of the circle flown by aircraft escorting a boat.
+    const ESCORT_RADIUS: f32 = 200.0;
+
     /// Allocates a new entity with some blank fields that should probably be populated, e.g. transform.
     pub fn new(entity_type: EntityType, player: Option<Arc<PlayerTuple<Server>>>) -> Self {
         Self {
@@ -333,11 +416,13 @@ impl Entity {
         )
     }
 
-    /// Updates the aim of all turrets, assuming delta_seconds have elapsed.
+    /// Updates the aim of all turrets, assuming delta_seconds have elapsed. Turrets lead the locked
+    /// contact, if any, instead of following the aim target.
     pub fn update_turret_aim(&mut self, delta_seconds: f32) {
-        let aim_target = if let Status::Alive { aim_target, .. } = &self.borrow_player().data.status
-        {
-            *aim_target
+        let (aim_target, target_velocity) = if let Some(lock) = &self.extension().lock {
+            (Some(lock.transform.position), lock.transform.velocity_vec())
+        } else if let Status::Alive { aim_target, .. } = &self.borrow_player().data.status {
+            (*aim_target, Vec2::ZERO)
         } else {
             panic!("boat's player was not alive in update_turret_aim()");
         };
@@ -346,10 +431,201 @@ impl Entity {
             self.transform,
             self.extension_mut().turrets_mut(),
             aim_target,
+            target_velocity,
             delta_seconds,
         );
     }
 
+    /// Steers a wire-guided weapon towards where its player is aiming (or to intercept the locked
+    /// contact), at a limited turn rate.
+    /// Returns false if the wire broke, because the launcher is gone, too fast, turning too fast,
+    /// or too far away.
+    pub fn follow_wire(
+        &mut self,
+        launchers: &HashMap<EntityIndex, Launcher>,
+        delta_seconds: f32,
+    ) -> bool {
+        let launcher = match self.launcher(launchers) {
+            Some(launcher) => launcher,
+            None => return false,
+        };
+
+        if launcher.transform.velocity.abs().to_mps() > Self::WIRE_MAX_LAUNCHER_SPEED
+            || launcher.turn_rate > Self::WIRE_MAX_LAUNCHER_TURN_RATE.to_radians()
+            || launcher
+                .transform
+                .position
+                .distance_squared(self.transform.position)
+                > Self::WIRE_LENGTH.powi(2)
+        {
+            return false;
+        }
+
+        let aim_target = if let Some(lock) = &launcher.lock {
+            Some(lead_intercept(
+                self.transform.position,
+                lock.position,
+                lock.velocity_vec(),
+                self.data().speed.to_mps(),
+            ))
+        } else if let Status::Alive { aim_target, .. } = self.borrow_player().data.status {
+            aim_target
+        } else {
+            None
+        };
+
+        if let Some(aim_target) = aim_target {
+            let angle = Angle::from(aim_target - self.transform.position);
+            let max_turn = Angle::from_degrees(Self::WIRE_TURN_RATE * delta_seconds);
+            self.guidance.direction_target +=
+                (angle - self.guidance.direction_target).clamp_magnitude(max_turn);
+        }
+        true
+    }
+
+    /// Returns the boat that launched a weapon or aircraft, if its player is still alive.
+    fn launcher<'a>(&self, launchers: &'a HashMap<EntityIndex, Launcher>) -> Option<&'a Launcher> {
+        if let Status::Alive { entity_index, .. } = self.borrow_player().data.status {
+            launchers.get(&entity_index)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the fraction of fuel an aircraft has remaining.
+    pub fn fuel(&self) -> f32 {
+        1.0 - self.ticks.to_secs() / self.data().lifespan.to_secs()
+    }
+
+    /// Returns true if an aircraft should return to its launcher.
+    pub fn is_low_on_fuel(&self) -> bool {
+        self.fuel() < Self::BINGO_FUEL
+    }
+
+    /// Burns an aircraft's fuel, which lasts its lifespan at half throttle, and burns faster at
+    /// full throttle.
+    pub fn burn_fuel(&mut self, delta: Ticks, rng: &mut impl Rng) {
+        let data = self.data();
+        let rate = map_ranges(
+            self.transform.velocity.abs().to_mps(),
+            0.0..data.speed.to_mps(),
+            0.5..1.5,
+            true,
+        );
+        let burn = delta.to_secs() * rate;
+
+        // Fuel is burned in whole ticks, so round randomly.
+        let whole = Ticks::from_secs(burn);
+        let fraction = (burn - whole.to_secs()) / Ticks::ONE.to_secs();
+        let burn = if rng.gen_bool(fraction.clamp(0.0, 1.0) as f64) {
+            whole + Ticks::ONE
+        } else {
+            whole
+        };
+        self.ticks = self.ticks.saturating_add(burn);
+    }
+
+    /// Returns where an aircraft should fly to carry out an order, or None if it no longer can.
+    pub fn squadron_target(
+        &self,
+        order: &SquadronOrder,
+        launchers: &HashMap<EntityIndex, Launcher>,
+        targets: &HashMap<EntityId, Vec2>,
+    ) -> Option<Vec2> {
+        match *order {
+            SquadronOrder::CombatAirPatrol(position) => {
+                Some(self.orbit(position, Self::PATROL_RADIUS))
+            }
+            SquadronOrder::Strike(id) => targets.get(&id).copied(),
+            SquadronOrder::Escort(id) => targets
+                .get(&id)
+                .map(|&position| self.orbit(position, Self::ESCORT_RADIUS)),
+            SquadronOrder::Return => self.nearest_landing_pad(launchers).map(|pad| pad.position),
+        }
+    }
+
+    /// Returns a point ahead on a circle around a center, such that following it circles the
+    /// center.
+    fn orbit(&self, center: Vec2, radius: f32) -> Vec2 {
+        let bearing = Angle::from(self.transform.position - center) + Angle::from_degrees(45.0);
+        center + bearing.to_vec() * radius
+    }
+
+    /// Returns the nearest landing pad an aircraft can return to, if any.
+    pub fn nearest_landing_pad(
+        &self,
+        launchers: &HashMap<EntityIndex, Launcher>,
+    ) -> Option<Transform> {
+        let position = self.transform.position;
+        self.launcher(launchers)?
+            .pads
+            .iter()
+            .filter(|(entity_type, _)| *entity_type == self.entity_type)
+            .map(|(_, pad)| *pad)
+            .min_by(|a, b| {
+                a.position
+                    .distance_squared(position)
+                    .partial_cmp(&b.position.distance_squared(position))
+                    .unwrap()
+            })
+    }
+
+    /// Steers towards the next waypoint of the boat's route, if any, stopping if there is a terrain
+    /// hazard in the way.
+    pub fn follow_route(&mut self, terrain: &Terrain) {
+        // How far ahead to look for terrain hazards.
+        const LOOKAHEAD: Ticks = Ticks::from_whole_secs(5);
+
+        let mut route = match self.extension_mut().route.take() {
+            Some(route) => route,
+            None => return,
+        };
+
+        // Skip any waypoints that were reached.
+        let reached_squared = self.data().length.max(50.0).powi(2);
+        let position = self.transform.position;
+        while route
+            .waypoints
+            .first()
+            .map_or(false, |w| w.distance_squared(position) < reached_squared)
+        {
+            route.waypoints.remove(0);
+        }
+
+        let next = match route.waypoints.first() {
+            Some(&next) => next,
+            None => {
+                // Arrived.
+                self.guidance.velocity_target = Velocity::ZERO;
+                self.extension_mut().route_blocked = false;
+                return;
+            }
+        };
+
+        let direction = Angle::from(next - position);
+        let speed = route.speed.abs();
+        let lookahead = LOOKAHEAD
+            .to_secs()
+            .min(position.distance(next) / speed.to_mps().max(1.0));
+
+        // Check the path to the next waypoint, at the route's speed.
+        let mut dim_transform = self.dimension_transform();
+        dim_transform.transform.direction = direction;
+        dim_transform.transform.velocity = speed;
+        let blocked = terrain
+            .collides_with(dim_transform, self.altitude.max(Altitude::ZERO), lookahead)
+            .is_some();
+
+        self.guidance = Guidance {
+            direction_target: direction,
+            velocity_target: if blocked { Velocity::ZERO } else { speed },
+        };
+
+        let extension = self.extension_mut();
+        extension.route = Some(route);
+        extension.route_blocked = blocked;
+    }
+
     /// Marks a particular armament as consumed.
     pub fn consume_armament(&mut self, index: usize) {
         let a = &self.data().armaments[index];
@@ -364,9 +640,12 @@ impl Entity {
         self.extension_mut().reloads_mut()[index] = reload;
     }
 
-    /// Repairs by a certain amount, up to maximum health.
+    /// Repairs by a certain amount, up to maximum health. Damaged components are repaired too.
     pub fn repair(&mut self, amount: Ticks) {
         self.ticks = self.ticks.saturating_sub(amount);
+        if self.is_boat() {
+            self.extension_mut().repair_components(amount);
+        }
     }
 
     /// Reloads arbitrary armaments/groups by a certain amount.
@@ -425,6 +704,119 @@ impl Entity {
         self.ticks == data.max_health()
     }
 
+    /// Like `damage`, but also damages the components near where the hit landed. Hits near a
+    /// turret disable it, hits near the stern damage the engines, and heavy hits damage sensors.
+    /// Returns if the entity died.
+    pub fn damage_at(&mut self, amount: Ticks, position: Vec2) -> bool {
+        // How close (relative to the dimensions) a hit must be to a turret to disable it.
+        const TURRET_REACH: f32 = 0.35;
+        // How far back (relative to the dimensions) the engines are.
+        const ENGINE_START: f32 = -0.5;
+        // What fraction of max health a hit must do to damage sensors.
+        const SENSOR_THRESHOLD: f32 = 0.25;
+
+        let data = self.data();
+        let dimension_transform = self.dimension_transform();
+        let local = dimension_transform.to_normalized_local(position);
+        let half_dimensions = dimension_transform.dimensions * 0.5;
+
+        let turrets = data.turrets.iter().enumerate().filter_map(|(i, turret)| {
+            (local.distance_squared(turret.position() / half_dimensions) < TURRET_REACH.powi(2))
+                .then_some(i)
+        });
+        let engine = local.x < ENGINE_START;
+        let sensors = amount >= data.max_health() * SENSOR_THRESHOLD;
+
+        self.extension_mut()
+            .damage_components(data, turrets, engine, sensors, amount);
+        self.damage(amount)
+    }
+
+    /// Starts a fire (shells, missiles and rockets) or flooding (torpedoes and mines), depending on
+    /// the weapon that hit the boat. Existing hazards are extended.
+    pub fn start_hazard(&mut self, attacker: &Arc<PlayerTuple<Server>>, weapon: EntityType) {
+        let (flooding, duration) = match weapon.data().sub_kind {
+            EntitySubKind::Shell | EntitySubKind::Missile | EntitySubKind::Rocket => {
+                (false, Self::FIRE_DURATION)
+            }
+            EntitySubKind::Torpedo | EntitySubKind::RocketTorpedo | EntitySubKind::Mine => {
+                (true, Self::FLOODING_DURATION)
+            }
+            _ => return,
+        };
+
+        // Submerged boats can't catch fire.
+        if !flooding && self.altitude.is_submerged() {
+            return;
+        }
+
+        let extension = self.extension_mut();
+        let hazard = if flooding {
+            &mut extension.flooding
+        } else {
+            &mut extension.fire
+        };
+
+        let remaining = hazard
+            .as_ref()
+            .map_or(duration, |h| h.remaining.max(duration));
+        *hazard = Some(Hazard {
+            remaining,
+            attacker: Arc::clone(attacker),
+            weapon,
+        });
+    }
+
+    /// Advances fires and flooding, returning the damage they deal this tick, along with who
+    /// started them and with what, if any. The damage is not applied.
+    pub fn update_hazards(
+        &mut self,
+        delta: Ticks,
+    ) -> Option<(Arc<PlayerTuple<Server>>, EntityType, Ticks)> {
+        let data = self.data();
+        let submerged = self.altitude.is_submerged();
+        let extension = self.extension_mut();
+
+        // Water puts out fires.
+        if submerged {
+            extension.fire = None;
+        }
+
+        let mut rate = 0.0;
+        let mut credit = None;
+        for (hazard, hazard_rate) in [
+            (&mut extension.flooding, Self::FLOODING_RATE),
+            (&mut extension.fire, Self::FIRE_RATE),
+        ] {
+            if let Some(h) = hazard {
+                // Resisting a weapon also means resisting the hazard it started.
+                rate += hazard_rate * data.resistance_to_subkind(h.weapon.data().sub_kind);
+                // Fire goes last, so it takes precedence.
+                credit = Some((Arc::clone(&h.attacker), h.weapon));
+
+                h.remaining = h.remaining.saturating_sub(delta);
+                if h.remaining == Ticks::ZERO {
+                    *hazard = None;
+                }
+            }
+        }
+
+        credit.map(|(attacker, weapon)| {
+            let damage = (data.max_health() * (rate * delta.to_secs())).max(Ticks::ONE);
+            (attacker, weapon, damage)
+        })
+    }
+
+    /// Returns the draft of a boat, which is increased by flooding.
+    pub fn draft(&self) -> Altitude {
+        let draft = self.data().draft;
+        if self.is_boat() && self.extension().flooding.is_some() {
+            draft + Self::FLOODING_DRAFT
+        } else {
+            draft
+        }
+    }
+
     /// Apply damage to ultimately kill an entity in kill_time, assuming delta ticks elapsed. Returns true if now dead.
     pub fn kill_in(&mut self, delta: Ticks, kill_time: Ticks) -> bool {
         self.damage(delta * (self.data().max_health() / kill_time).max(Ticks::ONE))
@@ -486,6 +878,7 @@ impl Entity {
                 _ => Altitude::ZERO,
             },
             EntityKind::Decoy => match data.sub_kind {
+                EntitySubKind::Chaff => Altitude::MAX,
                 EntitySubKind::Sonar => Altitude::MIN,
                 _ => Altitude::ZERO,
             },
@@ -531,7 +924,10 @@ impl Entity {
                 }
             },
             EntityKind::Decoy => match data.sub_kind {
+                EntitySubKind::Chaff => unguided_weapon_altitude,
                 EntitySubKind::Sonar => -unguided_weapon_altitude,
+                // Floats on the surface.
+                EntitySubKind::Sonobuoy => Altitude::ZERO,
                 _ => {
                     debug_assert!(false, "{:?}", data.sub_kind);
                     Altitude::ZERO
@@ -668,9 +1064,13 @@ impl Eq for Entity {}
 #[cfg(test)]
 mod tests {
     use crate::entity::Entity;
+    use crate::world_test::test_bot;
+    use common::altitude::Altitude;
     use common::entity::{EntityId, EntityType};
+    use common::ticks::Ticks;
     use glam::Vec2;
     use std::mem;
+    use std::sync::Arc;
 
     #[test]
     fn size_of() {
@@ -686,6 +1086,23 @@ mod tests {
             .collides_with(&Entity::new(EntityType::Crate, None), 0.0));
     }
 
+    #[test]
+    fn altitude_overlapping() {
+        unsafe {
+            EntityType::init();
+        }
+        // Unguided torpedoes run at this depth.
+        let mut torpedo = Entity::new(EntityType::Mark18, None);
+        torpedo.altitude = -Altitude::SPECIAL_OVERLAP_MARGIN;
+
+        let mut submarine = Entity::new(EntityType::Akula, None);
+        submarine.altitude = Altitude::PERISCOPE_DEPTH;
+        assert!(torpedo.altitude_overlapping(&submarine));
+
+        submarine.altitude = -EntityType::Akula.data().depth;
+        assert!(!torpedo.altitude_overlapping(&submarine));
+    }
+
     #[test]
     fn closest_point_on_keep_to() {
         unsafe {
@@ -710,4 +1127,89 @@ mod tests {
         assert_eq!(e2, e2);
         assert_ne!(e1, e2)
     }
+
+    #[test]
+    fn fire_burns_out() {
+        unsafe {
+            EntityType::init();
+        }
+        let attacker = test_bot(1);
+        let mut boat = Entity::new(EntityType::Yamato, Some(test_bot(2)));
+        boat.start_hazard(&attacker, EntityType::Mark8);
+
+        let mut elapsed = Ticks::ZERO;
+        while boat.extension().fire.is_some() {
+            let (credit, weapon, damage) = boat.update_hazards(Ticks::ONE).unwrap();
+            assert!(Arc::ptr_eq(&credit, &attacker));
+            assert_eq!(weapon, EntityType::Mark8);
+            assert!(damage > Ticks::ZERO);
+            elapsed += Ticks::ONE;
+        }
+        assert_eq!(elapsed, Entity::FIRE_DURATION);
+        assert!(boat.update_hazards(Ticks::ONE).is_none());
+    }
+
+    #[test]
+    fn submerging_puts_out_fire() {
+        unsafe {
+            EntityType::init();
+        }
+        let attacker = test_bot(1);
+        let mut submarine = Entity::new(EntityType::Akula, Some(test_bot(2)));
+        submarine.start_hazard(&attacker, EntityType::Mark8);
+        assert!(submarine.extension().fire.is_some());
+
+        submarine.altitude = Altitude::PERISCOPE_DEPTH;
+        assert!(submarine.update_hazards(Ticks::ONE).is_none());
+        assert!(submarine.extension().fire.is_none());
+
+        // Can't catch fire under water, but can still flood.
+        submarine.start_hazard(&attacker, EntityType::Mark8);
+        assert!(submarine.extension().fire.is_none());
+        submarine.start_hazard(&attacker, EntityType::Mark18);
+        assert!(submarine.extension().flooding.is_some());
+    }
+
+    #[test]
+    fn flooding_draft() {
+        unsafe {
+            EntityType::init();
+        }
+        let mut boat = Entity::new(EntityType::Yamato, Some(test_bot(2)));
+        let draft = boat.data().draft;
+        assert_eq!(boat.draft(), draft);
+
+        boat.start_hazard(&test_bot(1), EntityType::Mark18);
+        assert_eq!(boat.draft(), draft + Entity::FLOODING_DRAFT);
+
+        let mut elapsed = Ticks::ZERO;
+        while boat.update_hazards(Ticks::ONE).is_some() {
+            elapsed += Ticks::ONE;
+        }
+        assert_eq!(elapsed, Entity::FLOODING_DURATION);
+        assert_eq!(boat.draft(), draft);
+    }
+
+    #[test]
+    fn hazard_resistance() {
+        unsafe {
+            EntityType::init();
+        }
+        // Fires and flooding deal the same damage, but Yamato resists torpedoes.
+        let attacker = test_bot(1);
+        let mut burning = Entity::new(EntityType::Yamato, Some(test_bot(2)));
+        burning.start_hazard(&attacker, EntityType::Mark8);
+        let mut flooding = Entity::new(EntityType::Yamato, Some(test_bot(3)));
+        flooding.start_hazard(&attacker, EntityType::Mark18);
+
+        let delta = Ticks::from_whole_secs(1);
+        let (_, _, fire_damage) = burning.update_hazards(delta).unwrap();
+        let (_, _, flooding_damage) = flooding.update_hazards(delta).unwrap();
+        assert!(
+            flooding_damage < fire_damage,
+            "{:?} < {:?}",
+            flooding_damage,
+            fire_damage
+        );
+    }
 }
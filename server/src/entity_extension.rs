@@ -1,21 +1,45 @@
 // SPDX-FileCopyrightText: 2021 Softbear, Inc.
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::server::Server;
 use common::altitude::Altitude;
 use common::angle::Angle;
 use common::entity::*;
+use common::protocol::{ComponentDamage, Route};
 use common::ticks::Ticks;
+use common::transform::Transform;
 use common::util::make_mut_slice;
+use common_util::range::map_ranges;
+use game_server::player::PlayerTuple;
 use std::iter::FromIterator;
 use std::sync::Arc;
 
+/// A fire or flood started by a weapon, which damages a boat over time.
+#[derive(Debug)]
+pub struct Hazard {
+    /// How long until it burns out or is pumped out.
+    pub remaining: Ticks,
+    /// Who started it, to be credited for a kill.
+    pub attacker: Arc<PlayerTuple<Server>>,
+    /// What started it.
+    pub weapon: EntityType,
+}
+
+/// A contact that weapons are locked on to.
+#[derive(Debug)]
+pub struct TargetLock {
+    pub target: EntityId,
+    /// Where the target was last detected, and how it was moving.
+    pub transform: Transform,
+}
+
 /// Additional fields for certain entities (for now, boats). Stored separately for memory efficiency.
 #[derive(Debug)]
 pub struct EntityExtension {
-    // true means altitude target is Altitude::MIN false means Altitude::ZERO.
+    // Altitude that the player wants, which is between the boat's depth and Altitude::ZERO.
     // Used by Self::altitude_target().
     // Can't submerge right away to prevent dodging missiles.
-    submerge: bool,
+    altitude_target: Altitude,
     submerge_delay: Ticks,
 
     /// Whether the player *wants* active sensors. To tell if the player *has* active sensors, use
@@ -24,6 +48,16 @@ pub struct EntityExtension {
     active: bool,
     deactivate_delay: Ticks,
 
+    /// Fraction of endurance remaining, which limits how long submarines can stay submerged.
+    endurance: f32,
+    /// Endurance ran out, so the boat is forced up until it is fully recharged.
+    exhausted: bool,
+
+    /// Ticks until the close-in weapon system can fire again.
+    ciws_cooldown: Ticks,
+    /// Ticks of radar jamming remaining.
+    jamming: Ticks,
+
     /// Ticks of protection ticks remaining, zeroed if showing signs of aggression.
     spawn_protection_remaining: Ticks,
 
@@ -34,6 +68,29 @@ pub struct EntityExtension {
     // 1 angle per turret relative to boat.
     // Arc to save allocations
     pub turrets: Arc<[Angle]>,
+
+    /// Damage to each turret, which is disabled until repaired.
+    turret_damage: Box<[Ticks]>,
+    /// Damage to the engines, which slows the boat until repaired.
+    engine_damage: Ticks,
+    /// Damage to the sensors, which reduces their range until repaired.
+    sensor_damage: Ticks,
+
+    /// Fire, which is put out by submerging.
+    pub fire: Option<Hazard>,
+    /// Flooding, which slows the boat and increases its draft.
+    pub flooding: Option<Hazard>,
+
+    /// Landing pads whose aircraft returned for fuel, and will be relaunched once rearmed.
+    relaunches: Box<[bool]>,
+
+    /// Route being automatically followed, if any.
+    pub route: Option<Route>,
+    /// Whether following the route is blocked by a terrain hazard.
+    pub route_blocked: bool,
+
+    /// Contact that weapons are locked on to, if any.
+    pub lock: Option<TargetLock>,
 }
 
 fn arc_default_n<T: Default>(n: usize) -> Arc<[T]> {
@@ -52,6 +109,8 @@ impl EntityExtension {
     const DEACTIVATE_DELAY: Ticks = Ticks::from_repr(5);
     /// How long submerging is delayed.
     const SUBMERGE_DELAY: Ticks = Ticks::from_repr(8);
+    /// How long the close-in weapon system takes to cool down after firing.
+    const CIWS_COOLDOWN: Ticks = Ticks::from_repr(5);
 
     /// Allocates reloads and turrets, sized to a particular entity type.
     /// It can also give spawn protection.
@@ -65,24 +124,74 @@ impl EntityExtension {
             Ticks::ZERO
         };
         self.reloads = box_default_n(data.armaments.len());
+        self.relaunches = box_default_n(data.armaments.len());
         self.turrets = Arc::from_iter(data.turrets.iter().map(|t| t.angle));
+        self.turret_damage = box_default_n(data.turrets.len());
+        self.engine_damage = Ticks::ZERO;
+        self.sensor_damage = Ticks::ZERO;
+        self.clear_hazards();
+        self.jamming = Ticks::ZERO;
+        self.endurance = 1.0;
+        self.exhausted = false;
+        // Speed may no longer be attainable.
+        self.route = None;
+        self.route_blocked = false;
+        // Sensors may no longer detect the target.
+        self.lock = None;
     }
 
-    /// Returns the target altitude of the boat from submerge.
+    /// Returns the target altitude of the boat, which is the surface until done submerging, and
+    /// no deeper than snorkel depth while exhausted.
     pub fn altitude_target(&self) -> Altitude {
-        if self.submerge && self.submerge_delay == Ticks::ZERO {
-            Altitude::MIN
-        } else {
+        if self.submerge_delay != Ticks::ZERO {
             Altitude::ZERO
+        } else if self.exhausted {
+            self.altitude_target.max(Altitude::PERISCOPE_DEPTH)
+        } else {
+            self.altitude_target
+        }
+    }
+
+    /// Returns the fraction of endurance remaining.
+    pub fn endurance(&self) -> f32 {
+        self.endurance
+    }
+
+    /// Drains endurance while below snorkel depth (faster at high speed), and recharges it
+    /// otherwise.
+    pub fn update_endurance(
+        &mut self,
+        data: &EntityData,
+        altitude: Altitude,
+        speed: f32,
+        delta_seconds: f32,
+    ) {
+        if data.endurance == Ticks::ZERO {
+            return;
+        }
+
+        if altitude < Altitude::PERISCOPE_DEPTH {
+            let drain = map_ranges(speed / data.speed.to_mps(), 0.5..1.0, 1.0..3.0, true);
+            self.endurance -= delta_seconds * drain / data.endurance.to_secs();
+            if self.endurance <= 0.0 {
+                self.endurance = 0.0;
+                self.exhausted = true;
+            }
+        } else {
+            self.endurance += delta_seconds / data.recharge.to_secs().max(1.0);
+            if self.endurance >= 1.0 {
+                self.endurance = 1.0;
+                self.exhausted = false;
+            }
         }
     }
 
-    /// Sets submerge, possibly also setting deactivate_delay to an appropriate value.
-    pub fn set_submerge(&mut self, submerge: bool) {
-        if submerge && !self.submerge {
+    /// Sets altitude target, possibly also setting submerge_delay to an appropriate value.
+    pub fn set_altitude_target(&mut self, altitude_target: Altitude) {
+        if altitude_target.is_submerged() && !self.altitude_target.is_submerged() {
             self.submerge_delay = Self::SUBMERGE_DELAY;
         }
-        self.submerge = submerge;
+        self.altitude_target = altitude_target;
     }
 
     /// Returns whether active sensors, or within deactivate sensor delay.
@@ -118,6 +227,8 @@ impl EntityExtension {
         self.submerge_delay = self.submerge_delay.saturating_sub(delta);
         self.deactivate_delay = self.deactivate_delay.saturating_sub(delta);
         self.spawn_protection_remaining = self.spawn_protection_remaining.saturating_sub(delta);
+        self.ciws_cooldown = self.ciws_cooldown.saturating_sub(delta);
+        self.jamming = self.jamming.saturating_sub(delta);
     }
 
     /// reloads_mut returns a mutable reference to the reloads component of the extension.
@@ -129,6 +240,127 @@ impl EntityExtension {
     pub fn turrets_mut(&mut self) -> &mut [Angle] {
         make_mut_slice(&mut self.turrets)
     }
+
+    /// Returns true if the close-in weapon system is done cooling down.
+    pub fn ciws_ready(&self) -> bool {
+        self.ciws_cooldown == Ticks::ZERO
+    }
+
+    /// Starts the close-in weapon system cooldown.
+    pub fn fire_ciws(&mut self) {
+        self.ciws_cooldown = Self::CIWS_COOLDOWN;
+    }
+
+    /// Relaunches an armament (aircraft) automatically once it is rearmed.
+    pub fn set_relaunch(&mut self, index: usize) {
+        self.relaunches[index] = true;
+    }
+
+    /// Returns an armament that is rearmed and due to be relaunched, if any, so it isn't relaunched
+    /// again.
+    pub fn take_relaunch(&mut self) -> Option<usize> {
+        let reloads = &self.reloads;
+        let index = self
+            .relaunches
+            .iter()
+            .zip(reloads.iter())
+            .position(|(&relaunch, &reload)| relaunch && reload == Ticks::ZERO)?;
+        self.relaunches[index] = false;
+        Some(index)
+    }
+
+    /// Returns true if the boat's jammer is operating.
+    pub fn is_jamming(&self) -> bool {
+        self.jamming != Ticks::ZERO
+    }
+
+    /// Operates the boat's jammer for a certain duration.
+    pub fn start_jamming(&mut self, duration: Ticks) {
+        self.jamming = self.jamming.max(duration);
+    }
+
+    /// Returns true if a turret is damaged, preventing its armaments from firing.
+    pub fn turret_disabled(&self, index: usize) -> bool {
+        self.turret_damage[index] != Ticks::ZERO
+    }
+
+    /// Returns the fraction of max speed that damaged engines can attain.
+    pub fn engine_factor(&self, data: &EntityData) -> f32 {
+        Self::component_factor(self.engine_damage, data)
+    }
+
+    /// Returns the fraction of sensor range that damaged sensors can attain.
+    pub fn sensor_factor(&self, data: &EntityData) -> f32 {
+        Self::component_factor(self.sensor_damage, data)
+    }
+
+    fn component_factor(damage: Ticks, data: &EntityData) -> f32 {
+        map_ranges(
+            damage.to_secs() / data.max_health().to_secs(),
+            0.0..0.5,
+            1.0..0.5,
+            true,
+        )
+    }
+
+    /// Adds damage to components, each of which can't be damaged more than the boat's max health.
+    pub fn damage_components(
+        &mut self,
+        data: &EntityData,
+        turrets: impl IntoIterator<Item = usize>,
+        engine: bool,
+        sensors: bool,
+        amount: Ticks,
+    ) {
+        let max = data.max_health();
+        let add = |damage: &mut Ticks| *damage = damage.saturating_add(amount).min(max);
+
+        for index in turrets {
+            add(&mut self.turret_damage[index]);
+        }
+        if engine {
+            add(&mut self.engine_damage);
+        }
+        if sensors {
+            add(&mut self.sensor_damage);
+        }
+    }
+
+    /// Repairs all components by a certain amount.
+    pub fn repair_components(&mut self, amount: Ticks) {
+        for damage in self.turret_damage.iter_mut() {
+            *damage = damage.saturating_sub(amount);
+        }
+        self.engine_damage = self.engine_damage.saturating_sub(amount);
+        self.sensor_damage = self.sensor_damage.saturating_sub(amount);
+    }
+
+    /// Puts out fires and pumps out flooding, releasing references to attackers.
+    pub fn clear_hazards(&mut self) {
+        self.fire = None;
+        self.flooding = None;
+    }
+
+    /// Returns true if the boat is on fire or flooding.
+    pub fn has_hazard(&self) -> bool {
+        self.fire.is_some() || self.flooding.is_some()
+    }
+
+    /// Returns the components that are damaged, if any, to report to the owner.
+    pub fn component_damage(&self, data: &EntityData) -> Option<ComponentDamage> {
+        let disabled_turrets: Vec<u8> = (0..self.turret_damage.len())
+            .filter(|&i| self.turret_disabled(i))
+            .map(|i| i as u8)
+            .collect();
+        let engine = 1.0 - self.engine_factor(data);
+        let sensors = 1.0 - self.sensor_factor(data);
+
+        (!disabled_turrets.is_empty() || engine > 0.0 || sensors > 0.0).then(|| ComponentDamage {
+            disabled_turrets,
+            engine,
+            sensors,
+        })
+    }
 }
 
 impl Default for EntityExtension {
@@ -136,13 +368,104 @@ impl Default for EntityExtension {
     /// Once a boat is spawned/upgraded change_entity_type must be called.
     fn default() -> Self {
         Self {
-            submerge: false,
+            altitude_target: Altitude::ZERO,
+            endurance: 1.0,
+            exhausted: false,
             submerge_delay: Ticks::ZERO,
             active: true,
             deactivate_delay: Ticks::ZERO,
+            ciws_cooldown: Ticks::ZERO,
+            jamming: Ticks::ZERO,
             spawn_protection_remaining: Self::SPAWN_PROTECTION_INITIAL,
             reloads: box_default_n(0),
+            relaunches: box_default_n(0),
             turrets: arc_default_n(0),
+            turret_damage: box_default_n(0),
+            engine_damage: Ticks::ZERO,
+            sensor_damage: Ticks::ZERO,
+            fire: None,
+            flooding: None,
+            route: None,
+            route_blocked: false,
+            lock: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity_extension::EntityExtension;
+    use common::altitude::Altitude;
+    use common::entity::EntityType;
+    use common::ticks::Ticks;
+
+    #[test]
+    fn endurance() {
+        unsafe {
+            EntityType::init();
         }
+        let entity_type = EntityType::Akula;
+        let data = entity_type.data();
+        let depth = -data.depth;
+
+        let mut extension = EntityExtension::default();
+        extension.change_entity_type(entity_type);
+        extension.set_altitude_target(depth);
+        extension.submerge_delay = Ticks::ZERO;
+        assert_eq!(extension.altitude_target(), depth);
+
+        // Running at full speed drains faster than the rated endurance.
+        extension.update_endurance(
+            data,
+            depth,
+            data.speed.to_mps(),
+            data.endurance.to_secs() * 0.5,
+        );
+        assert_eq!(extension.endurance(), 0.0);
+        assert_eq!(extension.altitude_target(), Altitude::PERISCOPE_DEPTH);
+
+        // Forced up until fully recharged.
+        extension.update_endurance(
+            data,
+            Altitude::PERISCOPE_DEPTH,
+            0.0,
+            data.recharge.to_secs() * 0.5,
+        );
+        assert_eq!(extension.altitude_target(), Altitude::PERISCOPE_DEPTH);
+        extension.update_endurance(
+            data,
+            Altitude::PERISCOPE_DEPTH,
+            0.0,
+            data.recharge.to_secs(),
+        );
+        assert_eq!(extension.endurance(), 1.0);
+        assert_eq!(extension.altitude_target(), depth);
+    }
+
+    #[test]
+    fn component_damage() {
+        unsafe {
+            EntityType::init();
+        }
+        let entity_type = EntityType::Yamato;
+        let data = entity_type.data();
+
+        let mut extension = EntityExtension::default();
+        extension.change_entity_type(entity_type);
+        assert!(extension.component_damage(data).is_none());
+
+        let amount = data.max_health();
+        extension.damage_components(data, Some(0), true, false, amount);
+        assert!(extension.turret_disabled(0));
+        assert!(!extension.turret_disabled(1));
+        assert_eq!(extension.engine_factor(data), 0.5);
+        assert_eq!(extension.sensor_factor(data), 1.0);
+
+        let damage = extension.component_damage(data).unwrap();
+        assert_eq!(damage.disabled_turrets, vec![0]);
+        assert_eq!(damage.sensors, 0.0);
+
+        extension.repair_components(amount);
+        assert!(extension.component_damage(data).is_none());
     }
 }
@@ -16,6 +16,7 @@ use crate::server::Server;
 use common::entity::EntityType;
 
 mod arena;
+mod battle_royale;
 mod bot;
 mod collision;
 mod complete_ref;
@@ -26,16 +27,22 @@ mod entity_extension;
 mod noise;
 mod player;
 mod protocol;
+mod recording;
 mod server;
+mod territory;
 mod world;
+mod world_ciws;
 mod world_inbound;
+mod world_lock;
 mod world_mutation;
 mod world_outbound;
 mod world_physics;
 mod world_physics_radius;
 mod world_spawn;
+mod world_squadron;
 #[cfg(test)]
 mod world_test;
+mod world_weather;
 
 fn main() {
     // SAFETY: As per spec, only called once (before .data() is called).
@@ -48,6 +55,19 @@ fn main() {
         }
     }
 
+    // Headless replay of a recording made with MK48_RECORD.
+    if let Some(path) = std::env::var_os("MK48_REPLAY") {
+        let replay = recording::Replay::load(&path).expect("could not load recording");
+        let (world, counter) = replay.run(|_, _| {});
+        println!(
+            "replayed {} entries over {} ticks, ending with {} entities",
+            replay.entries.len(),
+            counter.0,
+            world.arena.total()
+        );
+        return;
+    }
+
     game_server::entry_point::entry_point::<Server>(
         minicdn::release_include_mini_cdn!("../../js/public"),
         false,
@@ -4,6 +4,7 @@
 use common::terrain;
 use common::terrain::*;
 use common_util::range::map_ranges;
+use glam::Vec2;
 use noise::{NoiseFn, SuperSimplex};
 use std::mem::MaybeUninit;
 
@@ -91,6 +92,23 @@ pub fn noise_generator(x: usize, y: usize) -> u8 {
     (height * 255.0) as u8
 }
 
+/// current generator returns the ocean current, in meters per second, for a given terrain chunk.
+pub fn current_generator(chunk_id: ChunkId) -> Vec2 {
+    /// Maximum speed of currents, in meters per second.
+    const MAX_SPEED: f32 = 3.0;
+    // Currents vary much more gradually than terrain.
+    const S: f64 = SCALE as f64 * 0.0012 * 0.25;
+
+    let Coord(x, y) = chunk_id.as_coord();
+    // Safety: See noise_generator.
+    let noise_x = x as f64 * S + unsafe { SEED };
+    let noise_y = y as f64 * S;
+
+    let u = fractal_noise(get_noise(), noise_x + 5000.0, noise_y, 2);
+    let v = fractal_noise(get_noise(), noise_x - 5000.0, noise_y, 2);
+    (Vec2::new(u as f32, v as f32) * MAX_SPEED).clamp_length_max(MAX_SPEED)
+}
+
 /// fractal noise returns multi-level noise for a given fractional coordinate.
 #[inline]
 fn fractal_noise(noise: &SuperSimplex, x: f64, y: f64, octaves: u32) -> f64 {
@@ -26,6 +26,9 @@ impl AsCommandTrait for Command {
             Command::Control(ref v) => v as &dyn CommandTrait,
             Command::Spawn(ref v) => v as &dyn CommandTrait,
             Command::Upgrade(ref v) => v as &dyn CommandTrait,
+            Command::Route(ref v) => v as &dyn CommandTrait,
+            Command::Squadron(ref v) => v as &dyn CommandTrait,
+            Command::Lock(ref v) => v as &dyn CommandTrait,
         }
     }
 }
@@ -0,0 +1,381 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+//! Recording of everything that influences a match, and a headless replay of such recordings, for
+//! investigating disputed kills and physics glitches after the fact.
+//!
+//! A recording is a bincode encoded [`RecordingHeader`], followed by any number of bincode encoded
+//! [`RecordedEntry`]s. Replays rely on the world being deterministic, given its seed. Wall clock
+//! based behavior (terrain regeneration, spawn exclusion) is not reproduced exactly.
+
+use crate::battle_royale::BattleRoyale;
+use crate::noise;
+use crate::protocol::AsCommandTrait;
+use crate::server::Server;
+use crate::territory::Territory;
+use crate::world::World;
+use common::protocol::Command;
+use common::terrain::ChunkId;
+use common::ticks::Ticks;
+use core_protocol::id::{PlayerId, TeamId};
+use game_server::player::{PlayerData, PlayerTuple};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Incremented whenever the format changes.
+pub const RECORDING_VERSION: u16 = 9;
+
+/// Everything required to reconstruct the initial state of a world.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u16,
+    /// See [`World::seed`].
+    pub world_seed: u64,
+    pub world_radius: f32,
+    /// Whether [`World::battle_royale`] was enabled.
+    pub battle_royale: bool,
+    /// Whether [`World::territory`] was enabled.
+    pub territory: bool,
+    /// See [`noise::SEED`].
+    pub terrain_seed: f64,
+    /// Chunks that were already generated (and possibly modified), encoded with `Chunk::to_bytes`.
+    pub terrain_chunks: Vec<(ChunkId, Vec<u8>)>,
+}
+
+/// An input to the world by a player.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RecordedEvent {
+    /// Player joined with a certain score.
+    Joined {
+        score: u32,
+    },
+    Command(Command),
+    /// Player's team changed to team_id, or to no team.
+    ChangedTeam {
+        team_id: Option<TeamId>,
+    },
+    Left,
+}
+
+/// A [`RecordedEvent`], along with when and by whom.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RecordedEntry {
+    /// Value of `Server::counter` when the event happened.
+    pub counter: Ticks,
+    pub player_id: PlayerId,
+    pub event: RecordedEvent,
+}
+
+/// Appends entries to a recording file.
+pub struct Recorder {
+    writer: BufWriter<File>,
+}
+
+impl Recorder {
+    /// Creates a recording file, and writes the header, based on the current state of the world.
+    pub fn create(path: impl AsRef<Path>, world: &World) -> io::Result<Self> {
+        let header = RecordingHeader {
+            version: RECORDING_VERSION,
+            world_seed: world.seed,
+            world_radius: world.radius,
+            battle_royale: world.battle_royale.is_some(),
+            territory: world.territory.is_some(),
+            // Safety: Seed is only ever modified for testing purposes.
+            terrain_seed: unsafe { noise::SEED },
+            terrain_chunks: world
+                .terrain
+                .iter_chunks()
+                .map(|(chunk_id, chunk)| (chunk_id, chunk.to_bytes()))
+                .collect(),
+        };
+
+        let mut writer = BufWriter::new(File::create(path)?);
+        bincode::serialize_into(&mut writer, &header).map_err(into_io_error)?;
+        Ok(Self { writer })
+    }
+
+    /// Appends one entry (buffered).
+    pub fn record(&mut self, counter: Ticks, player_id: PlayerId, event: RecordedEvent) {
+        let entry = RecordedEntry {
+            counter,
+            player_id,
+            event,
+        };
+        if let Err(e) = bincode::serialize_into(&mut self.writer, &entry) {
+            warn!("could not record {:?}: {}", entry, e);
+        }
+    }
+
+    /// Flushes buffered entries to the file.
+    pub fn flush(&mut self) {
+        if let Err(e) = self.writer.flush() {
+            warn!("could not flush recording: {}", e);
+        }
+    }
+}
+
+/// A recording, loaded into memory.
+pub struct Replay {
+    pub header: RecordingHeader,
+    pub entries: Vec<RecordedEntry>,
+}
+
+impl Replay {
+    /// Loads a recording file.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_reader(BufReader::new(File::open(path)?))
+    }
+
+    /// Loads a recording from any reader, stopping at the end of the last complete entry.
+    pub fn from_reader(mut reader: impl Read) -> io::Result<Self> {
+        let header: RecordingHeader =
+            bincode::deserialize_from(&mut reader).map_err(into_io_error)?;
+
+        if header.version != RECORDING_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unsupported recording version {}", header.version),
+            ));
+        }
+
+        let mut entries = Vec::new();
+        loop {
+            match bincode::deserialize_from(&mut reader) {
+                Ok(entry) => entries.push(entry),
+                Err(e) => match *e {
+                    // A truncated entry is expected if the server didn't shut down cleanly.
+                    bincode::ErrorKind::Io(ref io) if io.kind() == io::ErrorKind::UnexpectedEof => {
+                        break
+                    }
+                    _ => return Err(into_io_error(e)),
+                },
+            }
+        }
+
+        Ok(Self { header, entries })
+    }
+
+    /// Creates a fresh world, identical to the recorded world's initial state.
+    pub fn initial_world(&self) -> World {
+        unsafe {
+            // SAFETY: Replays are run headlessly, so there are no other threads accessing the
+            // terrain.
+            noise::SEED = self.header.terrain_seed;
+        }
+
+        let mut world = World::with_seed(self.header.world_radius, self.header.world_seed);
+        if self.header.battle_royale {
+            world.battle_royale = Some(BattleRoyale::new());
+        }
+        if self.header.territory {
+            world.territory = Some(Territory::new());
+        }
+        for (chunk_id, bytes) in &self.header.terrain_chunks {
+            world.terrain.load_chunk(*chunk_id, bytes);
+        }
+        world
+    }
+
+    /// Feeds the recording into a fresh world, calling inspect after every tick. Returns the
+    /// final world and its tick counter. All players are solo in the returned world, as players
+    /// outside of an arena must be solo when dropped.
+    pub fn run(&self, mut inspect: impl FnMut(Ticks, &World)) -> (World, Ticks) {
+        let mut world = self.initial_world();
+        let mut players: HashMap<PlayerId, Arc<PlayerTuple<Server>>> = HashMap::new();
+        let mut counter = Ticks::ZERO;
+
+        for entry in &self.entries {
+            // Mirrors Server::tick.
+            while counter < entry.counter {
+                counter = counter.next();
+                world.update(Ticks::ONE);
+                world.terrain.pre_update();
+                world.terrain.post_update(Ticks::ONE, &mut world.rng);
+                inspect(counter, &world);
+            }
+
+            let player_tuple = players.entry(entry.player_id).or_insert_with(|| {
+                Arc::new(PlayerTuple::new(PlayerData::new(entry.player_id, None)))
+            });
+
+            match &entry.event {
+                RecordedEvent::Joined { score } => {
+                    player_tuple.borrow_player_mut().score = *score;
+                    Server::join(&mut world, player_tuple);
+                }
+                RecordedEvent::Command(command) => {
+                    // Errors are expected, as they were also recorded.
+                    let _ = command.as_command().apply(&mut world, player_tuple);
+                }
+                RecordedEvent::ChangedTeam { team_id } => {
+                    let old_team = player_tuple.borrow_player().team_id();
+                    player_tuple
+                        .borrow_player_mut()
+                        .set_team_id_outside_arena(*team_id);
+                    Server::change_team(player_tuple, old_team);
+                }
+                RecordedEvent::Left => Server::leave(player_tuple),
+            }
+        }
+
+        for player_tuple in players.values() {
+            player_tuple
+                .borrow_player_mut()
+                .set_team_id_outside_arena(None);
+        }
+
+        (world, counter)
+    }
+}
+
+fn into_io_error(e: bincode::Error) -> io::Error {
+    match *e {
+        bincode::ErrorKind::Io(io) => io,
+        e => io::Error::new(io::ErrorKind::InvalidData, e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::bot::Bot;
+    use crate::protocol::AsCommandTrait;
+    use crate::recording::{RecordedEvent, Recorder, Replay};
+    use crate::world::World;
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::entity::EntityType;
+    use common::ticks::Ticks;
+    use core_protocol::id::TeamId;
+    use game_server::game_service::BotAction;
+    use game_server::player::PlayerTuple;
+    use glam::Vec2;
+    use std::num::NonZeroU32;
+    use std::sync::Arc;
+
+    /// Changes a player's team, as the arena would, and records it.
+    fn set_team(
+        recorder: &mut Recorder,
+        counter: Ticks,
+        player: &Arc<PlayerTuple<Server>>,
+        team_id: Option<TeamId>,
+    ) {
+        let old_team = player.borrow_player().team_id();
+        player
+            .borrow_player_mut()
+            .set_team_id_outside_arena(team_id);
+        let player_id = player.borrow_player().player_id;
+        recorder.record(counter, player_id, RecordedEvent::ChangedTeam { team_id });
+        Server::change_team(player, old_team);
+    }
+
+    #[test]
+    fn replay() {
+        crate::noise::init();
+        unsafe { EntityType::init() };
+
+        let path = std::env::temp_dir().join("mk48_replay_test.bin");
+
+        let mut world = World::with_seed(2000.0, 42);
+        let mut recorder = Recorder::create(&path, &world).unwrap();
+        let mut counter = Ticks::ZERO;
+        let team_id = TeamId(NonZeroU32::new(1).unwrap());
+
+        let mut bots: Vec<_> = (0..10)
+            .map(|i| {
+                let player = test_bot(i);
+                let player_id = player.borrow_player().player_id;
+                let score = player.borrow_player().score;
+                recorder.record(counter, player_id, RecordedEvent::Joined { score });
+                Server::join(&mut world, &player);
+                // Half of the bots are on a team, so friendliness must be replayed.
+                if i % 2 == 0 {
+                    set_team(&mut recorder, counter, &player, Some(team_id));
+                }
+                (player, Bot::with_seed(i as u64))
+            })
+            .collect();
+
+        for _ in 0..200 {
+            for (player, bot) in bots.iter_mut() {
+                let player_id = player.borrow_player().player_id;
+                if let BotAction::Some(command) = bot.update(
+                    world.get_player_complete(player),
+                    player_id,
+                    world.players_can_spawn(),
+                ) {
+                    recorder.record(counter, player_id, RecordedEvent::Command(command.clone()));
+                    let _ = command.as_command().apply(&mut world, player);
+                }
+            }
+            counter = counter.next();
+            world.update(Ticks::ONE);
+            world.terrain.pre_update();
+            world.terrain.post_update(Ticks::ONE, &mut world.rng);
+        }
+        for (player, _) in &bots {
+            set_team(&mut recorder, counter, player, None);
+        }
+        recorder.flush();
+        drop(recorder);
+
+        let replay = Replay::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        let (mut replayed, mut replayed_counter) = replay.run(|_, _| {});
+
+        // Catch up to the end (the last ticks may not have had any entries).
+        while replayed_counter < counter {
+            replayed_counter = replayed_counter.next();
+            replayed.update(Ticks::ONE);
+        }
+
+        assert_eq!(world.test_snapshot(), replayed.test_snapshot());
+    }
+
+    #[test]
+    fn replay_currents() {
+        crate::noise::init();
+        unsafe { EntityType::init() };
+
+        let path = std::env::temp_dir().join("mk48_replay_currents_test.bin");
+
+        // Generate terrain before recording starts, as on a server that has been running.
+        let mut world = World::with_seed(2000.0, 43);
+        let positions: Vec<Vec2> = (-4..=4)
+            .flat_map(|x| (-4..=4).map(move |y| Vec2::new(x as f32, y as f32) * 500.0))
+            .collect();
+        for &position in &positions {
+            world.terrain.sample(position);
+        }
+        assert!(positions
+            .iter()
+            .any(|&position| world.terrain.current(position) != Vec2::ZERO));
+
+        let mut recorder = Recorder::create(&path, &world).unwrap();
+        recorder.flush();
+        drop(recorder);
+
+        let replay = Replay::load(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let mut replayed = replay.initial_world();
+
+        for &position in &positions {
+            assert_eq!(
+                world.terrain.current(position),
+                replayed.terrain.current(position)
+            );
+        }
+
+        // Statics that spawn in the water drift the same way.
+        for _ in 0..100 {
+            world.update(Ticks::ONE);
+            replayed.update(Ticks::ONE);
+        }
+        assert_eq!(world.test_snapshot(), replayed.test_snapshot());
+    }
+}
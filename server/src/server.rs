@@ -1,10 +1,13 @@
 // SPDX-FileCopyrightText: 2021 Softbear, Inc.
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::battle_royale::BattleRoyale;
 use crate::bot::*;
 use crate::entity_extension::EntityExtension;
 use crate::player::*;
 use crate::protocol::*;
+use crate::recording::{RecordedEvent, Recorder};
+use crate::territory::Territory;
 use crate::world::World;
 use common::entity::EntityType;
 use common::protocol::{Command, Update};
@@ -23,6 +26,8 @@ use std::time::Duration;
 pub struct Server {
     pub world: World,
     pub counter: Ticks,
+    /// Records inputs to the world, if enabled by the `MK48_RECORD` environment variable.
+    pub recorder: Option<Recorder>,
 }
 
 /// Stores a player, and metadata related to it. Data stored here may only be accessed when processing,
@@ -58,11 +63,30 @@ impl GameArenaService for Server {
 
     /// new returns a game server with the specified parameters.
     fn new(min_players: usize) -> Self {
+        let mut world = World::new(World::target_radius(
+            min_players as f32 * EntityType::FairmileD.data().visual_area(),
+        ));
+
+        // Round based mode, for events.
+        if std::env::var_os("MK48_BATTLE_ROYALE").is_some() {
+            world.battle_royale = Some(BattleRoyale::new());
+        }
+
+        // HQs and oil platforms are capture points, for teams.
+        if std::env::var_os("MK48_TERRITORY").is_some() {
+            world.territory = Some(Territory::new());
+        }
+
+        let recorder = std::env::var_os("MK48_RECORD").and_then(|path| {
+            Recorder::create(&path, &world)
+                .map_err(|e| warn!("could not record to {:?}: {}", path, e))
+                .ok()
+        });
+
         Self {
-            world: World::new(World::target_radius(
-                min_players as f32 * EntityType::FairmileD.data().visual_area(),
-            )),
+            world,
             counter: Ticks::ZERO,
+            recorder,
         }
     }
 
@@ -81,20 +105,10 @@ impl GameArenaService for Server {
         player_tuple: &Arc<PlayerTuple<Self>>,
         _players: &PlayerRepo<Server>,
     ) {
-        let mut player = player_tuple.borrow_player_mut();
-        player.data.flags.left_game = false;
-        #[cfg(debug_assertions)]
-        {
-            use common::entity::EntityData;
-            use common::util::level_to_score;
-            use rand::{thread_rng, Rng};
-            let highest_level_score = level_to_score(EntityData::MAX_BOAT_LEVEL);
-            player.score = if player.is_bot() {
-                thread_rng().gen_range(0..=highest_level_score)
-            } else {
-                highest_level_score
-            };
-        }
+        self.record(player_tuple, || RecordedEvent::Joined {
+            score: player_tuple.borrow_player().score,
+        });
+        Self::join(&mut self.world, player_tuple);
     }
 
     fn player_command(
@@ -103,6 +117,8 @@ impl GameArenaService for Server {
         player: &Arc<PlayerTuple<Self>>,
         _players: &PlayerRepo<Server>,
     ) -> Option<Update> {
+        // Commands are recorded even if they fail, as failing commands may have partial effects.
+        self.record(player, || RecordedEvent::Command(update.clone()));
         if let Err(e) = update.as_command().apply(&mut self.world, player) {
             warn!("Command resulted in {}", e);
         }
@@ -115,13 +131,10 @@ impl GameArenaService for Server {
         old_team: Option<TeamId>,
         _players: &PlayerRepo<Server>,
     ) {
-        if old_team.is_some() {
-            player_tuple
-                .borrow_player_mut()
-                .data
-                .flags
-                .left_populated_team = true;
-        }
+        self.record(player_tuple, || RecordedEvent::ChangedTeam {
+            team_id: player_tuple.borrow_player().team_id(),
+        });
+        Self::change_team(player_tuple, old_team);
     }
 
     fn player_left(
@@ -129,21 +142,8 @@ impl GameArenaService for Server {
         player_tuple: &Arc<PlayerTuple<Self>>,
         _players: &PlayerRepo<Server>,
     ) {
-        let mut player = player_tuple.borrow_player_mut();
-        if player.status.is_alive() {
-            drop(player);
-        } else {
-            player.data.status = Status::Spawning;
-            drop(player);
-        }
-
-        let mut player = player_tuple.borrow_player_mut();
-
-        // Clear player's score.
-        player.score = 0;
-
-        // Delete all player's entities (efficiently, in the next update cycle).
-        player.data.flags.left_game = true;
+        self.record(player_tuple, || RecordedEvent::Left);
+        Self::leave(player_tuple);
     }
 
     fn get_game_update(
@@ -165,17 +165,93 @@ impl GameArenaService for Server {
     }
 
     /// update runs server ticks.
-    fn tick(&mut self, _context: &mut Context<Self>) {
+    fn tick(&mut self, context: &mut Context<Self>) {
         self.counter = self.counter.next();
 
         self.world.update(Ticks::ONE);
 
+        if let Some(battle_royale) = self.world.battle_royale.as_mut() {
+            for announcement in battle_royale.take_announcements() {
+                context.broadcast_authority_message(announcement);
+            }
+        }
+
         // Needs to be called before clients receive updates, but after World::update.
         self.world.terrain.pre_update();
+
+        if let Some(recorder) = self.recorder.as_mut() {
+            recorder.flush();
+        }
     }
 
     fn post_update(&mut self, _context: &mut Context<Self>) {
         // Needs to be after clients receive updates.
-        self.world.terrain.post_update();
+        self.world
+            .terrain
+            .post_update(Ticks::ONE, &mut self.world.rng);
+    }
+}
+
+impl Server {
+    /// Prepares a player that joined the game (also used when replaying recordings).
+    pub fn join(world: &mut World, player_tuple: &Arc<PlayerTuple<Self>>) {
+        let mut player = player_tuple.borrow_player_mut();
+        player.data.flags.left_game = false;
+        #[cfg(debug_assertions)]
+        {
+            use common::entity::EntityData;
+            use common::util::level_to_score;
+            use rand::Rng;
+            let highest_level_score = level_to_score(EntityData::MAX_BOAT_LEVEL);
+            player.score = if player.is_bot() {
+                world.rng.gen_range(0..=highest_level_score)
+            } else {
+                highest_level_score
+            };
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = world;
+    }
+
+    /// Cleans up after a player that left the game (also used when replaying recordings).
+    pub fn leave(player_tuple: &Arc<PlayerTuple<Self>>) {
+        let mut player = player_tuple.borrow_player_mut();
+        if player.status.is_alive() {
+            drop(player);
+        } else {
+            player.data.status = Status::Spawning;
+            drop(player);
+        }
+
+        let mut player = player_tuple.borrow_player_mut();
+
+        // Clear player's score.
+        player.score = 0;
+
+        // Delete all player's entities (efficiently, in the next update cycle).
+        player.data.flags.left_game = true;
+    }
+
+    /// Handles a player that changed team (also used when replaying recordings).
+    pub fn change_team(player_tuple: &Arc<PlayerTuple<Self>>, old_team: Option<TeamId>) {
+        if old_team.is_some() {
+            player_tuple
+                .borrow_player_mut()
+                .data
+                .flags
+                .left_populated_team = true;
+        }
+    }
+
+    /// Records an event caused by a player, if recording is enabled.
+    fn record(
+        &mut self,
+        player_tuple: &Arc<PlayerTuple<Self>>,
+        event: impl FnOnce() -> RecordedEvent,
+    ) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            let player_id = player_tuple.borrow_player().player_id;
+            recorder.record(self.counter, player_id, event());
+        }
     }
 }
@@ -0,0 +1,299 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::server::Server;
+use crate::world::World;
+use common::entity::{EntityId, EntityType};
+use common::protocol::CapturePoint;
+use common::ticks::Ticks;
+use core_protocol::id::TeamId;
+use game_server::player::PlayerTuple;
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+/// Territory control mode, in which HQs and oil platforms are capture points that periodically pay
+/// score to the team that holds them.
+#[derive(Default)]
+pub struct Territory {
+    /// State of each capture point, by the id of the HQ or oil platform.
+    points: BTreeMap<EntityId, PointState>,
+    /// Time since score was last paid.
+    since_payout: Ticks,
+}
+
+#[derive(Default)]
+struct PointState {
+    /// Team that last captured the point.
+    owner: Option<TeamId>,
+    /// Team that is capturing the point, and for how long it has been doing so.
+    capturing: Option<(TeamId, Ticks)>,
+    /// Whether the owner has boats in range, and no one else does.
+    held: bool,
+}
+
+/// Who has boats in range of a capture point.
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Presence {
+    Empty,
+    Team(TeamId),
+    /// Multiple teams, or players without a team.
+    Contested,
+}
+
+impl Territory {
+    /// Boats must be this close to a capture point to capture, hold, or contest it.
+    const RANGE: f32 = 400.0;
+    /// How long a team must be alone in range of a capture point to capture it.
+    const CAPTURE_TIME: Ticks = Ticks::from_whole_secs(15);
+    /// How often held capture points pay.
+    const PAYOUT_PERIOD: Ticks = Ticks::from_whole_secs(10);
+    /// Score paid to each member (with a boat) of the owning team, per held capture point.
+    const PAYOUT_SCORE: u32 = 5;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns true if entities of the given type are capture points.
+    pub fn is_capture_point(entity_type: EntityType) -> bool {
+        matches!(entity_type, EntityType::Hq | EntityType::OilPlatform)
+    }
+
+    /// Returns all capture points that are owned by a team, to be sent to clients.
+    pub fn capture_points(&self) -> Vec<CapturePoint> {
+        self.points
+            .iter()
+            .filter_map(|(&entity_id, point)| {
+                point.owner.map(|owner| CapturePoint {
+                    entity_id,
+                    owner,
+                    held: point.held,
+                })
+            })
+            .collect()
+    }
+
+    /// Advances the payout timer, returning how many held capture points each team is paid for
+    /// (empty if it isn't time to pay).
+    fn payouts(&mut self, delta: Ticks) -> BTreeMap<TeamId, u32> {
+        let mut held_counts = BTreeMap::new();
+
+        self.since_payout = self.since_payout.saturating_add(delta);
+        if self.since_payout < Self::PAYOUT_PERIOD {
+            return held_counts;
+        }
+        self.since_payout = Ticks::ZERO;
+
+        for owner in self
+            .points
+            .values()
+            .filter(|point| point.held)
+            .filter_map(|point| point.owner)
+        {
+            *held_counts.entry(owner).or_default() += 1;
+        }
+        held_counts
+    }
+}
+
+impl PointState {
+    /// Advances the capture of the point, given who has boats in range of it.
+    fn update(&mut self, presence: Presence, delta: Ticks) {
+        match presence {
+            Presence::Team(team_id) if self.owner != Some(team_id) => {
+                let elapsed = match self.capturing {
+                    Some((capturing, elapsed)) if capturing == team_id => {
+                        elapsed.saturating_add(delta)
+                    }
+                    _ => delta,
+                };
+
+                if elapsed >= Territory::CAPTURE_TIME {
+                    self.owner = Some(team_id);
+                    self.capturing = None;
+                } else {
+                    self.capturing = Some((team_id, elapsed));
+                }
+            }
+            // Progress is paused while contested, but lost if the capturing team leaves.
+            Presence::Contested => {}
+            _ => self.capturing = None,
+        }
+
+        self.held = self.owner.map(Presence::Team) == Some(presence);
+    }
+}
+
+impl World {
+    /// Updates the ownership of capture points, and pays their owners, if territory mode is
+    /// enabled.
+    pub fn update_territory(&mut self, delta: Ticks) {
+        if self.territory.is_none() {
+            return;
+        }
+
+        let entities = &self.entities;
+        let presences: BTreeMap<EntityId, Presence> = entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, entity)| Territory::is_capture_point(entity.entity_type))
+            .map(|(_, entity)| {
+                let mut presence = Presence::Empty;
+                for (_, boat) in entities.iter_radius(entity.transform.position, Territory::RANGE) {
+                    if !boat.is_boat() {
+                        continue;
+                    }
+                    presence = match (presence, boat.borrow_player().team_id()) {
+                        (Presence::Empty, Some(team_id)) => Presence::Team(team_id),
+                        (Presence::Team(a), Some(b)) if a == b => presence,
+                        _ => Presence::Contested,
+                    };
+                }
+                (entity.id, presence)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .collect();
+
+        let territory = self.territory.as_mut().unwrap();
+
+        // Forget capture points that no longer exist.
+        territory
+            .points
+            .retain(|entity_id, _| presences.contains_key(entity_id));
+
+        for (entity_id, presence) in presences {
+            territory
+                .points
+                .entry(entity_id)
+                .or_default()
+                .update(presence, delta);
+        }
+
+        let held_counts = territory.payouts(delta);
+        if held_counts.is_empty() {
+            return;
+        }
+
+        let players: Vec<Arc<PlayerTuple<Server>>> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, entity)| entity.is_boat())
+            .map(|(_, entity)| Arc::clone(entity.player.as_ref().unwrap()))
+            .collect();
+
+        for player_tuple in players {
+            let mut player = player_tuple.borrow_player_mut();
+            if let Some(count) = player.team_id().and_then(|t| held_counts.get(&t)) {
+                player.score += count * Territory::PAYOUT_SCORE;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::territory::{PointState, Presence, Territory};
+    use common::entity::EntityType;
+    use common::ticks::Ticks;
+    use core_protocol::id::TeamId;
+    use std::num::NonZeroU32;
+
+    fn team(n: u32) -> TeamId {
+        TeamId(NonZeroU32::new(n).unwrap())
+    }
+
+    #[test]
+    fn capture_progress() {
+        let mut point = PointState::default();
+        let half = Territory::CAPTURE_TIME * 0.5;
+
+        point.update(Presence::Team(team(1)), half);
+        assert_eq!(point.owner, None);
+        assert!(!point.held);
+
+        // Leaving loses progress.
+        point.update(Presence::Empty, Ticks::ONE);
+        assert_eq!(point.capturing, None);
+
+        point.update(Presence::Team(team(1)), half);
+        point.update(Presence::Team(team(1)), Territory::CAPTURE_TIME - half);
+        assert_eq!(point.owner, Some(team(1)));
+        assert_eq!(point.capturing, None);
+        assert!(point.held);
+
+        // Owned points aren't held once the owner leaves, but stay owned.
+        point.update(Presence::Empty, Ticks::ONE);
+        assert_eq!(point.owner, Some(team(1)));
+        assert!(!point.held);
+    }
+
+    #[test]
+    fn contested() {
+        let mut point = PointState::default();
+        let half = Territory::CAPTURE_TIME * 0.5;
+
+        // Progress is paused, not lost, while contested.
+        point.update(Presence::Team(team(1)), half);
+        point.update(Presence::Contested, Territory::CAPTURE_TIME);
+        assert_eq!(point.owner, None);
+        assert_eq!(point.capturing, Some((team(1), half)));
+
+        point.update(Presence::Team(team(1)), Territory::CAPTURE_TIME - half);
+        assert_eq!(point.owner, Some(team(1)));
+
+        // Another team contesting the point stops it from being held.
+        point.update(Presence::Contested, Ticks::ONE);
+        assert_eq!(point.owner, Some(team(1)));
+        assert!(!point.held);
+
+        // Another team must be alone for the full capture time to take it over.
+        point.update(
+            Presence::Team(team(2)),
+            Territory::CAPTURE_TIME - Ticks::ONE,
+        );
+        assert_eq!(point.owner, Some(team(1)));
+        point.update(Presence::Team(team(2)), Ticks::ONE);
+        assert_eq!(point.owner, Some(team(2)));
+    }
+
+    #[test]
+    fn scoring() {
+        assert!(Territory::is_capture_point(EntityType::Hq));
+        assert!(Territory::is_capture_point(EntityType::OilPlatform));
+        assert!(!Territory::is_capture_point(EntityType::Crate));
+
+        let mut territory = Territory::new();
+        let owners = [Some(team(1)), Some(team(1)), Some(team(2)), None];
+        for (i, owner) in owners.iter().enumerate() {
+            let mut point = PointState::default();
+            if let Some(owner) = *owner {
+                point.update(Presence::Team(owner), Territory::CAPTURE_TIME);
+            }
+            territory
+                .points
+                .insert(NonZeroU32::new(i as u32 + 1).unwrap(), point);
+        }
+
+        // Team 2 left its point.
+        territory
+            .points
+            .values_mut()
+            .nth(2)
+            .unwrap()
+            .update(Presence::Empty, Ticks::ONE);
+
+        assert!(territory
+            .payouts(Territory::PAYOUT_PERIOD - Ticks::ONE)
+            .is_empty());
+        let payouts = territory.payouts(Ticks::ONE);
+        assert_eq!(payouts.get(&team(1)), Some(&2));
+        assert_eq!(payouts.get(&team(2)), None);
+        assert_eq!(territory.capture_points().len(), 3);
+
+        // Paid once per period.
+        assert!(territory.payouts(Ticks::ONE).is_empty());
+    }
+}
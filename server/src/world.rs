@@ -2,14 +2,24 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::arena::Arena;
+use crate::battle_royale::BattleRoyale;
 use crate::entities::{Entities, EntityIndex};
 use crate::entity::Entity;
-use crate::noise::noise_generator;
+use crate::noise::{current_generator, noise_generator};
+use crate::territory::Territory;
 use crate::world_mutation::Mutation;
+use crate::world_outbound::Sensor;
 use common::death_reason::DeathReason;
-use common::entity::{EntityKind, EntityType};
+use common::entity::{EntityId, EntityKind, EntityType};
+use common::protocol::{CiwsBurst, SquadronOrder};
 use common::terrain::Terrain;
 use common::ticks::Ticks;
+use common::time_of_day::TimeOfDay;
+use common::weather::WeatherCell;
+use glam::Vec2;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+use std::collections::{HashMap, HashSet};
 
 /// A game world of variable radius, consisting of entities and a terrain.
 pub struct World {
@@ -17,26 +27,90 @@ pub struct World {
     pub entities: Entities,
     pub terrain: Terrain,
     pub radius: f32,
+    /// Center of the border, which is the origin unless moved by battle royale.
+    pub center: Vec2,
+    /// Round based mode, if enabled.
+    pub battle_royale: Option<BattleRoyale>,
+    /// Capture point mode, if enabled.
+    pub territory: Option<Territory>,
+    /// Drifting regions of fog, rain, and storms.
+    pub weather: Vec<WeatherCell>,
+    /// Clock of the day/night cycle.
+    pub time_of_day: TimeOfDay,
+    /// Bursts fired by close-in weapon systems (and the boats that fired them) during the last
+    /// update.
+    pub ciws_bursts: Vec<(EntityId, CiwsBurst)>,
+    /// Boats (and where they were) removed during the last update, so clients can tell sinking
+    /// apart from losing contact.
+    pub sunk: Vec<(EntityId, Vec2)>,
+    /// Weapons that are still steered by wire from their launcher.
+    pub wires: HashSet<EntityId>,
+    /// Orders given to aircraft, which otherwise follow their player's aim target.
+    pub squadrons: HashMap<EntityId, SquadronOrder>,
+    /// Sensors of all players as of the last update, so their detections can be shared.
+    pub(crate) sensors: Vec<Sensor>,
+    /// Seed the world was created with.
+    pub seed: u64,
+    /// Source of all randomness in the simulation, such that the same seed and the same commands
+    /// result in the same world.
+    pub rng: StdRng,
 }
 
 impl World {
-    /// Creates a new World with the given parameters.
+    /// Creates a new World with the given parameters and a random seed.
     pub fn new(initial_radius: f32) -> Self {
+        Self::with_seed(initial_radius, thread_rng().gen())
+    }
+
+    /// Creates a new World with the given parameters, which will evolve deterministically.
+    pub fn with_seed(initial_radius: f32, seed: u64) -> Self {
         Self {
             arena: Arena::new(),
             entities: Entities::new(),
-            terrain: Terrain::with_generator(noise_generator),
+            terrain: Terrain::with_generators(noise_generator, current_generator),
             radius: initial_radius,
+            center: Vec2::ZERO,
+            battle_royale: None,
+            territory: None,
+            weather: Vec::new(),
+            time_of_day: TimeOfDay::default(),
+            ciws_bursts: Vec::new(),
+            sunk: Vec::new(),
+            wires: HashSet::new(),
+            squadrons: HashMap::new(),
+            sensors: Vec::new(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
         }
     }
 
+    /// Returns an rng seeded by a per-tick seed (drawn from the world rng before a parallel loop)
+    /// and a key (such as an EntityId), which is safe to use in parallel iterators without
+    /// sacrificing determinism.
+    pub fn keyed_rng(tick_seed: u64, key: u64) -> StdRng {
+        StdRng::seed_from_u64(tick_seed ^ key.wrapping_mul(0x9E3779B97F4A7C15))
+    }
+
     /// Updates the internals of the world, spawning and updating existing entities.
     pub fn update(&mut self, delta: Ticks) {
+        self.sunk.clear();
+        self.time_of_day = self.time_of_day.advance(delta);
         self.spawn_statics(delta);
+        self.update_weather(delta);
+        self.update_locks();
+        self.update_squadrons();
         self.physics(delta);
         self.physics_radius(delta);
+        self.update_ciws();
+        self.update_territory(delta);
+        self.sensors = self.gather_sensors();
         self.arena.recycle();
 
+        if self.update_battle_royale(delta) {
+            // Border is on a schedule.
+            return;
+        }
+
         let total_visual_area = EntityType::iter()
             .map(|t| {
                 let data = t.data();
@@ -60,9 +134,11 @@ impl World {
     }
 
     /// Adds an entity to the world (assigning it an id).
-    pub fn add(&mut self, mut entity: Entity) {
-        entity.id = self.arena.new_id(entity.entity_type);
-        self.entities.add_internal(entity)
+    pub fn add(&mut self, mut entity: Entity) -> EntityId {
+        let id = self.arena.new_id(entity.entity_type);
+        entity.id = id;
+        self.entities.add_internal(entity);
+        id
     }
 
     /// Removes an entity from the world with a given index and death reason.
@@ -70,9 +146,22 @@ impl World {
     pub fn remove(&mut self, index: EntityIndex, reason: DeathReason) {
         Mutation::on_world_remove(self, index, &reason);
         let entity = self.entities.remove_internal(index, reason);
+        if entity.is_boat() {
+            self.sunk.push((entity.id, entity.transform.position));
+        }
+        self.wires.remove(&entity.id);
+        self.squadrons.remove(&entity.id);
         self.arena.drop_entity(entity);
     }
 
+    /// Returns true if players may currently spawn (false during a battle royale round).
+    pub fn players_can_spawn(&self) -> bool {
+        self.battle_royale
+            .as_ref()
+            .map(|battle_royale| battle_royale.can_spawn())
+            .unwrap_or(true)
+    }
+
     /// Returns the area of the world, based on it's radius.
     pub fn area(&self) -> f32 {
         self.radius.powi(2) * std::f32::consts::PI
@@ -0,0 +1,219 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::entities::EntityIndex;
+use crate::entity::Entity;
+use crate::world::World;
+use common::death_reason::DeathReason;
+use common::entity::{EntityKind, EntitySubKind};
+use common::protocol::CiwsBurst;
+use common_util::range::map_ranges;
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+use rand::Rng;
+
+impl World {
+    /// Crossing speed (in meters per second) at which close-in weapon systems are least effective.
+    const CIWS_MAX_CROSSING_SPEED: f32 = 250.0;
+
+    /// Returns true if a close-in weapon system would engage the entity.
+    fn is_ciws_target(entity: &Entity) -> bool {
+        let data = entity.data();
+        !entity.altitude.is_submerged()
+            && match data.kind {
+                EntityKind::Aircraft => true,
+                EntityKind::Weapon => {
+                    matches!(
+                        data.sub_kind,
+                        EntitySubKind::Missile | EntitySubKind::Rocket
+                    )
+                }
+                _ => false,
+            }
+    }
+
+    /// Close-in weapon systems on boats with anti-aircraft guns fire at the most threatening
+    /// incoming aircraft, missile, or rocket in range, with a chance of destroying it that
+    /// decreases with its crossing speed.
+    pub fn update_ciws(&mut self) {
+        // Boats are processed in parallel, so they can't share the world rng.
+        let tick_seed: u64 = self.rng.gen();
+        let entities = &self.entities;
+
+        let mut engagements: Vec<(EntityIndex, EntityIndex, CiwsBurst, bool)> = entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter_map(|(index, boat)| {
+                let data = boat.data();
+                if data.kind != EntityKind::Boat
+                    || data.anti_aircraft == 0.0
+                    || boat.altitude.is_submerged()
+                    || !boat.extension().ciws_ready()
+                {
+                    return None;
+                }
+
+                let boat_velocity =
+                    boat.transform.direction.to_vec() * boat.transform.velocity.to_mps();
+
+                // Most threatening has the least time to impact.
+                let (target_index, target, crossing_speed, _) = entities
+                    .iter_radius(boat.transform.position, data.anti_aircraft_range())
+                    .filter(|(_, target)| Self::is_ciws_target(target) && !boat.is_friendly(target))
+                    .filter_map(|(target_index, target)| {
+                        let diff = boat.transform.position - target.transform.position;
+                        let distance = diff.length();
+                        if distance < 1.0 {
+                            return None;
+                        }
+                        let normal = diff / distance;
+                        let velocity = target.transform.direction.to_vec()
+                            * target.transform.velocity.to_mps()
+                            - boat_velocity;
+
+                        let closing_speed = velocity.dot(normal);
+                        if closing_speed <= 0.0 {
+                            // Receding, so not a threat.
+                            return None;
+                        }
+                        let crossing_speed = velocity.perp_dot(normal).abs();
+                        let time_to_impact = distance / closing_speed;
+                        Some((target_index, target, crossing_speed, time_to_impact))
+                    })
+                    .min_by(|a, b| a.3.partial_cmp(&b.3).unwrap())?;
+
+                let probability = data.anti_aircraft
+                    * 2.0
+                    * map_ranges(
+                        crossing_speed,
+                        0.0..Self::CIWS_MAX_CROSSING_SPEED,
+                        1.0..0.25,
+                        true,
+                    );
+                let kill = Self::keyed_rng(tick_seed, boat.id.get() as u64)
+                    .gen_bool(probability.clamp(0.0, 1.0) as f64);
+
+                let burst = CiwsBurst {
+                    position: boat.closest_point_on_keel_to(target.transform.position, 0.8),
+                    target: target.transform.position,
+                };
+
+                Some((index, target_index, burst, kill))
+            })
+            .collect();
+
+        // Parallel iteration may collect in an arbitrary order.
+        engagements.sort_by_key(|(index, _, _, _)| *index);
+
+        self.ciws_bursts.clear();
+        let mut kills = Vec::new();
+        for (index, target_index, burst, kill) in engagements {
+            let boat = &mut self.entities[index];
+            boat.extension_mut().fire_ciws();
+            self.ciws_bursts.push((boat.id, burst));
+            if kill {
+                kills.push(target_index);
+            }
+        }
+
+        // Sorted in reverse to remove correctly, and multiple boats may kill the same target.
+        kills.sort_unstable_by(|a, b| b.cmp(a));
+        kills.dedup();
+        for index in kills {
+            self.remove(index, DeathReason::Unknown);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Entity;
+    use crate::world::World;
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::angle::Angle;
+    use common::entity::EntityType;
+    use common::terrain::Terrain;
+    use common::velocity::Velocity;
+    use game_server::player::PlayerTuple;
+    use glam::{vec2, Vec2};
+    use std::sync::Arc;
+
+    /// Spawns a boat with a close-in weapon system at the origin.
+    fn world_with_boat(player: &Arc<PlayerTuple<Server>>) -> World {
+        unsafe {
+            EntityType::init();
+        }
+        let mut world = World::new(2000.0);
+        world.terrain = Terrain::new();
+        let boat = Entity::new(EntityType::ArleighBurke, Some(Arc::clone(player)));
+        assert!(world.spawn_here_or_nearby(boat, 0.0, None).is_some());
+        world
+    }
+
+    /// Spawns a missile flying in a direction.
+    fn spawn_missile(
+        world: &mut World,
+        player: &Arc<PlayerTuple<Server>>,
+        position: Vec2,
+        direction: Angle,
+    ) {
+        let mut missile = Entity::new(EntityType::Harpoon, Some(Arc::clone(player)));
+        missile.transform.position = position;
+        missile.transform.direction = direction;
+        missile.transform.velocity = Velocity::from_mps(200.0);
+        assert!(world.spawn_here_or_nearby(missile, 0.0, None).is_some());
+    }
+
+    #[test]
+    fn incoming_first() {
+        let defender = test_bot(2);
+        let attacker = test_bot(3);
+        let mut world = world_with_boat(&defender);
+
+        let incoming = vec2(100.0, 0.0);
+        let receding = vec2(0.0, 90.0);
+        spawn_missile(&mut world, &attacker, receding, Angle::from(receding));
+        spawn_missile(&mut world, &attacker, incoming, Angle::from(-incoming));
+
+        world.update_ciws();
+        assert_eq!(world.ciws_bursts.len(), 1);
+        assert_eq!(world.ciws_bursts[0].1.target, incoming);
+
+        // Cooling down.
+        world.update_ciws();
+        assert!(world.ciws_bursts.is_empty());
+    }
+
+    #[test]
+    fn receding_ignored() {
+        let defender = test_bot(2);
+        let attacker = test_bot(3);
+        let mut world = world_with_boat(&defender);
+
+        let position = vec2(0.0, 90.0);
+        spawn_missile(&mut world, &attacker, position, Angle::from(position));
+
+        world.update_ciws();
+        assert!(world.ciws_bursts.is_empty());
+        let index = defender
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        assert!(world.entities[index].extension().ciws_ready());
+    }
+
+    #[test]
+    fn friendly_ignored() {
+        let defender = test_bot(2);
+        let mut world = world_with_boat(&defender);
+
+        let position = vec2(100.0, 0.0);
+        spawn_missile(&mut world, &defender, position, Angle::from(-position));
+
+        world.update_ciws();
+        assert!(world.ciws_bursts.is_empty());
+        assert_eq!(world.entities.iter_radius(Vec2::ZERO, 1000.0).count(), 2);
+    }
+}
@@ -1,23 +1,27 @@
 // SPDX-FileCopyrightText: 2021 Softbear, Inc.
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
+use crate::entities::EntityIndex;
 use crate::entity::Entity;
+use crate::entity_extension::TargetLock;
 use crate::player::Status;
 use crate::protocol::*;
 use crate::server::Server;
 use crate::world::World;
+use common::altitude::Altitude;
 use common::angle::Angle;
 use common::entity::*;
 use common::protocol::*;
 use common::terrain::TerrainMutation;
 use common::ticks::Ticks;
-use common::util::{level_to_score, score_to_level};
+use common::util::{lead_intercept, level_to_score, score_to_level};
+use common::velocity::Velocity;
 use common::world::{clamp_y_to_strict_area_border, outside_strict_area, ARCTIC};
 use common_util::range::map_ranges;
 use game_server::player::PlayerTuple;
 use glam::Vec2;
 use maybe_parallel_iterator::IntoMaybeParallelIterator;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::ops::Range;
 use std::sync::Arc;
 use std::time::Duration;
@@ -42,6 +46,10 @@ impl CommandTrait for Spawn {
             return Err("cannot spawn while already alive");
         }
 
+        if !world.players_can_spawn() {
+            return Err("cannot spawn during battle royale round");
+        }
+
         if !self.entity_type.can_spawn_as(player.score, player.is_bot()) {
             return Err("cannot spawn as given entity type");
         }
@@ -50,7 +58,7 @@ impl CommandTrait for Spawn {
         let mut spawn_position = Vec2::ZERO;
         let mut spawn_radius = 0.8 * world.radius;
 
-        let mut rng = thread_rng();
+        let rng = &mut world.rng;
 
         if !(player.is_bot() && rng.gen()) {
             // Default to spawning near the center of the world, with more points making you spawn further north.
@@ -127,21 +135,22 @@ impl CommandTrait for Spawn {
         if player.team_id().is_some() || player.invitation_accepted().is_some() {
             // TODO: Inefficient to scan all entities; only need to scan all players. Unfortunately,
             // that data is not available here, currently.
-            if let Some((_, team_boat)) = world
+            // Pick the lowest index, as opposed to any, to remain deterministic.
+            let team_boat_index = world
                 .entities
                 .par_iter()
                 .into_maybe_parallel_iter()
-                .find_any(|(_, entity)| {
+                .filter_map(|(index, entity)| {
                     let data = entity.data();
                     if data.kind != EntityKind::Boat {
-                        return false;
+                        return None;
                     }
 
                     if let Some(exclusion_zone) = exclusion_zone {
                         if entity.transform.position.distance_squared(exclusion_zone)
                             < 1100f32.powi(2)
                         {
-                            return false;
+                            return None;
                         }
                     }
 
@@ -152,9 +161,13 @@ impl CommandTrait for Spawn {
                         && entity.borrow_player().player_id
                             == player.invitation_accepted().as_ref().unwrap().player_id;
 
-                    is_team_member || was_invited_by
+                    (is_team_member || was_invited_by).then_some(index)
                 })
-            {
+                .collect::<Vec<_>>()
+                .into_iter()
+                .min();
+
+            if let Some(team_boat) = team_boat_index.map(|index| &world.entities[index]) {
                 spawn_position = team_boat.transform.position;
                 spawn_radius = team_boat.data().radius + 25.0;
             }
@@ -166,7 +179,10 @@ impl CommandTrait for Spawn {
         boat.transform.position = spawn_position;
         //#[cfg(debug_assertions)]
         //let begin = std::time::Instant::now();
-        if world.spawn_here_or_nearby(boat, spawn_radius, exclusion_zone) {
+        if world
+            .spawn_here_or_nearby(boat, spawn_radius, exclusion_zone)
+            .is_some()
+        {
             /*
             #[cfg(debug_assertions)]
             println!(
@@ -192,6 +208,7 @@ impl CommandTrait for Control {
 
         // Pre-borrow.
         let world_radius = world.radius;
+        let world_center = world.center;
 
         return if let Status::Alive {
             entity_index,
@@ -204,9 +221,14 @@ impl CommandTrait for Control {
             // Movement
             if let Some(guidance) = self.guidance {
                 entity.guidance = guidance;
+                // Manual steering overrides the route.
+                entity.extension_mut().route = None;
             }
             *aim_target = if let Some(mut aim_target) = self.aim_target {
+                // Relative to the border, which isn't necessarily centered on the origin.
+                aim_target -= world_center;
                 sanitize_floats(aim_target.as_mut(), -world_radius * 2.0..world_radius * 2.0)?;
+                aim_target += world_center;
                 Some(
                     (aim_target - entity.transform.position)
                         .clamp_length_max(entity.data().sensors.max_range())
@@ -215,8 +237,9 @@ impl CommandTrait for Control {
             } else {
                 None
             };
+            let depth = entity.data().depth;
             let extension = entity.extension_mut();
-            extension.set_submerge(self.submerge);
+            extension.set_altitude_target(self.altitude_target.clamp(-depth, Altitude::ZERO));
             extension.set_active(self.active);
 
             drop(player);
@@ -225,6 +248,10 @@ impl CommandTrait for Control {
                 fire.apply(world, player_tuple)?;
             }
 
+            if let Some(salvo) = &self.salvo {
+                salvo.apply(world, player_tuple)?;
+            }
+
             if let Some(pay) = &self.pay {
                 pay.apply(world, player_tuple)?;
             }
@@ -240,6 +267,163 @@ impl CommandTrait for Control {
     }
 }
 
+impl CommandTrait for Lock {
+    fn apply(
+        &self,
+        world: &mut World,
+        player_tuple: &Arc<PlayerTuple<Server>>,
+    ) -> Result<(), &'static str> {
+        let player = player_tuple.borrow_player();
+
+        let entity_index = if let Status::Alive { entity_index, .. } = player.data.status {
+            entity_index
+        } else {
+            return Err("cannot lock while not alive");
+        };
+        drop(player);
+
+        let lock = if let Some(id) = self.target {
+            let boat = &world.entities[entity_index];
+            let (_, target) = world
+                .entities
+                .iter_radius(boat.transform.position, boat.data().sensors.max_range())
+                .find(|(_, target)| target.id == id)
+                .ok_or("lock target not in range")?;
+
+            if !World::can_lock(boat, target) {
+                return Err("can only lock on to non-friendly boats, aircraft and weapons");
+            }
+
+            if !world.detects(boat, target) {
+                return Err("lock target not detected");
+            }
+
+            Some(TargetLock {
+                target: id,
+                transform: target.transform,
+            })
+        } else {
+            None
+        };
+
+        world.entities[entity_index].extension_mut().lock = lock;
+        Ok(())
+    }
+}
+
+impl CommandTrait for Route {
+    fn apply(
+        &self,
+        world: &mut World,
+        player_tuple: &Arc<PlayerTuple<Server>>,
+    ) -> Result<(), &'static str> {
+        let player = player_tuple.borrow_player();
+
+        let entity_index = if let Status::Alive { entity_index, .. } = player.data.status {
+            entity_index
+        } else {
+            return Err("cannot route while not alive");
+        };
+
+        if self.waypoints.len() > Self::MAX_WAYPOINTS {
+            return Err("too many waypoints");
+        }
+
+        let mut waypoints = self.waypoints.clone();
+        for waypoint in &mut waypoints {
+            *waypoint -= world.center;
+            sanitize_floats(waypoint.as_mut(), -world.radius..world.radius)?;
+            *waypoint += world.center;
+        }
+
+        let entity = &mut world.entities[entity_index];
+        let max_speed = entity.data().speed;
+        let speed = self.speed.clamp(Velocity::ZERO, max_speed);
+
+        let extension = entity.extension_mut();
+        extension.route = (!waypoints.is_empty()).then(|| Route { waypoints, speed });
+        extension.route_blocked = false;
+        Ok(())
+    }
+}
+
+impl CommandTrait for Squadron {
+    fn apply(
+        &self,
+        world: &mut World,
+        player_tuple: &Arc<PlayerTuple<Server>>,
+    ) -> Result<(), &'static str> {
+        let player = player_tuple.borrow_player();
+
+        let entity_index = if let Status::Alive { entity_index, .. } = player.data.status {
+            entity_index
+        } else {
+            return Err("cannot order squadron while not alive");
+        };
+        drop(player);
+
+        let boat = &world.entities[entity_index];
+
+        let order = match self.order {
+            Some(SquadronOrder::CombatAirPatrol(mut position)) => {
+                position -= world.center;
+                sanitize_floats(position.as_mut(), -world.radius..world.radius)?;
+                Some(SquadronOrder::CombatAirPatrol(position + world.center))
+            }
+            Some(order) => {
+                if let Some(id) = order.target() {
+                    // Can only order aircraft to contacts that are detected (or friendly). Contacts
+                    // shared by teammates or heard by sonobuoys are too imprecise to target.
+                    let (_, target) = world
+                        .entities
+                        .iter_radius(boat.transform.position, boat.data().sensors.max_range())
+                        .find(|(_, target)| target.id == id)
+                        .filter(|(_, target)| {
+                            target.is_friendly(boat) || world.detects(boat, target)
+                        })
+                        .ok_or("squadron target not detected")?;
+
+                    if matches!(order, SquadronOrder::Escort(_))
+                        && !(target.is_boat() && target.is_friendly(boat))
+                    {
+                        return Err("can only escort friendly boats");
+                    }
+                }
+                Some(order)
+            }
+            None => None,
+        };
+
+        let aircraft: Vec<EntityId> = world
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, entity)| {
+                let data = entity.data();
+                data.kind == EntityKind::Aircraft
+                    && self
+                        .sub_kind
+                        .map_or(true, |sub_kind| data.sub_kind == sub_kind)
+                    && entity.has_same_player(boat)
+            })
+            .map(|(_, entity)| entity.id)
+            .collect();
+
+        if aircraft.is_empty() {
+            return Err("no aircraft to order");
+        }
+
+        for id in aircraft {
+            if let Some(order) = order {
+                world.squadrons.insert(id, order);
+            } else {
+                world.squadrons.remove(&id);
+            }
+        }
+        Ok(())
+    }
+}
+
 impl CommandTrait for Fire {
     fn apply(
         &self,
@@ -258,117 +442,264 @@ impl CommandTrait for Fire {
             if player.data.flags.upgraded {
                 return Err("cannot fire right after upgrading");
             }
+            drop(player);
 
-            let entity = &mut world.entities[entity_index];
+            let index = self.armament_index as usize;
+            check_armament(&world.entities[entity_index], index)?;
+            fire_armament(
+                world,
+                player_tuple,
+                entity_index,
+                index,
+                aim_target,
+                Angle::ZERO,
+            )
+        } else {
+            Err("cannot fire while not alive")
+        };
+    }
+}
 
-            let data = entity.data();
+impl CommandTrait for Salvo {
+    fn apply(
+        &self,
+        world: &mut World,
+        player_tuple: &Arc<PlayerTuple<Server>>,
+    ) -> Result<(), &'static str> {
+        let player = player_tuple.borrow_player();
 
-            let index = self.armament_index as usize;
-            if index >= data.armaments.len() {
-                return Err("armament index out of bounds");
+        return if let Status::Alive {
+            entity_index,
+            aim_target,
+            ..
+        } = player.data.status
+        {
+            // Same as Fire.
+            if player.data.flags.upgraded {
+                return Err("cannot fire right after upgrading");
             }
+            drop(player);
 
-            if entity.extension().reloads[index] != Ticks::ZERO {
-                return Err("armament not yet reloaded");
+            let entity = &world.entities[entity_index];
+            let index = self.armament_index as usize;
+            check_armament(entity, index)?;
+
+            // Check all armaments before firing any, such that each is fired at most once, and
+            // the fan is centered on those that have room to launch.
+            let armaments = &entity.data().armaments;
+            let group: Vec<usize> = armaments
+                .iter()
+                .enumerate()
+                .filter(|(i, armament)| {
+                    armament.is_similar_to(&armaments[index])
+                        && check_armament(entity, *i).is_ok()
+                        && can_launch(world, entity, *i)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            if group.is_empty() {
+                return Err("failed to fire from current location");
             }
 
-            let armament = &data.armaments[index];
-            let armament_entity_data = armament.entity_type.data();
-
-            // Can't fire if boat is a submerged former submarine.
-            if entity.altitude.is_submerged()
-                && (data.sub_kind != EntitySubKind::Submarine
-                    || matches!(armament_entity_data.kind, EntityKind::Aircraft)
-                    || matches!(
-                        armament_entity_data.sub_kind,
-                        EntitySubKind::Shell | EntitySubKind::Sam
-                    ))
-            {
-                return Err("cannot fire while surfacing as a boat");
+            // Torpedoes fan out symmetrically around the aim.
+            let spread = if armaments[index].entity_type.data().sub_kind == EntitySubKind::Torpedo {
+                self.spread
+                    .clamp_magnitude(Angle::from_degrees(Self::MAX_SPREAD_DEGREES))
+            } else {
+                Angle::ZERO
+            };
+            let center = (group.len() - 1) as f32 * 0.5;
+
+            // An armament that still fails to fire is skipped, since those already fired can't be
+            // taken back.
+            let mut result = Err("no armament fired");
+            for (i, index) in group.into_iter().enumerate() {
+                let offset = spread * (i as f32 - center);
+                match fire_armament(world, player_tuple, entity_index, index, aim_target, offset) {
+                    Ok(()) => result = Ok(()),
+                    Err(e) if result.is_err() => result = Err(e),
+                    Err(_) => {}
+                }
             }
+            result
+        } else {
+            Err("cannot fire while not alive")
+        };
+    }
+}
 
-            if let Some(turret_index) = armament.turret {
-                let turret_angle = entity.extension().turrets[turret_index];
-                let turret = &data.turrets[turret_index];
+/// Returns an error if an armament can't currently be fired.
+fn check_armament(entity: &Entity, index: usize) -> Result<(), &'static str> {
+    let data = entity.data();
 
-                // The aim may be outside the range but the turret must not be fired if the turret's
-                // current angle is outside the range.
-                if !turret.within_azimuth(turret_angle) {
-                    return Err("invalid turret azimuth");
-                }
-            }
+    if index >= data.armaments.len() {
+        return Err("armament index out of bounds");
+    }
 
-            let armament_transform =
-                entity.transform + data.armament_transform(&entity.extension().turrets, index);
+    if entity.extension().reloads[index] != Ticks::ZERO {
+        return Err("armament not yet reloaded");
+    }
 
-            if armament_entity_data.sub_kind == EntitySubKind::Depositor {
-                if let Some(mut target) = aim_target {
-                    // Can't deposit in arctic.
-                    target.y = target.y.min(ARCTIC - 2.0 * common::terrain::SCALE);
+    let armament = &data.armaments[index];
+    let armament_entity_data = armament.entity_type.data();
+
+    // Can't fire if boat is a submerged former submarine.
+    if entity.altitude.is_submerged()
+        && (data.sub_kind != EntitySubKind::Submarine
+            || matches!(armament_entity_data.kind, EntityKind::Aircraft)
+            || matches!(
+                armament_entity_data.sub_kind,
+                EntitySubKind::Shell
+                    | EntitySubKind::Sam
+                    | EntitySubKind::Chaff
+                    | EntitySubKind::Jammer
+                    | EntitySubKind::Sonobuoy
+            ))
+    {
+        return Err("cannot fire while surfacing as a boat");
+    }
 
-                    let depositor = armament_transform.position;
+    if let Some(turret_index) = armament.turret {
+        if entity.extension().turret_disabled(turret_index) {
+            return Err("turret disabled");
+        }
 
-                    // Radius of depositor.
-                    const MAX_RADIUS: f32 = 60.0;
+        let turret_angle = entity.extension().turrets[turret_index];
+        let turret = &data.turrets[turret_index];
 
-                    // Max radius that will snap to MAX_RADIUS.
-                    const CUTOFF_RADIUS: f32 = MAX_RADIUS * 2.0;
+        // The aim may be outside the range but the turret must not be fired if the turret's
+        // current angle is outside the range.
+        if !turret.within_azimuth(turret_angle) {
+            return Err("invalid turret azimuth");
+        }
+    }
 
-                    // Make sure target is in valid range.
-                    let delta = target - depositor;
-                    if delta.length_squared() > CUTOFF_RADIUS.powi(2) {
-                        return Err("outside maximum range");
-                    }
-                    let pos = depositor + delta.clamp_length_max(MAX_RADIUS);
+    Ok(())
+}
 
-                    world.terrain.modify(TerrainMutation::simple(pos, 60.0));
-                } else {
-                    return Err("cannot deposit without aim target");
-                }
-            } else {
-                // Fire weapon.
-                let player_arc = Arc::clone(player_tuple);
+/// Returns true if an armament's weapon, decoy, or aircraft would have room to launch. Unlike
+/// boats, these don't block each other, so a whole salvo can be checked before firing.
+fn can_launch(world: &World, entity: &Entity, index: usize) -> bool {
+    let data = entity.data();
+    let armament = &data.armaments[index];
+    if matches!(
+        armament.entity_type.data().sub_kind,
+        EntitySubKind::Depositor | EntitySubKind::Jammer
+    ) {
+        // Not launched.
+        return true;
+    }
 
-                drop(player);
-                let mut armament_entity = Entity::new(armament.entity_type, Some(player_arc));
+    let mut armament_entity = Entity::new(armament.entity_type, entity.player.clone());
+    armament_entity.transform =
+        entity.transform + data.armament_transform(&entity.extension().turrets, index);
+    armament_entity.altitude = entity.altitude;
+    world.can_spawn(&armament_entity, 1.0, world.radius)
+}
 
-                armament_entity.transform = armament_transform;
-                armament_entity.altitude = entity.altitude;
+/// Fires an armament that passed `check_armament`, turned by an offset from the aim.
+fn fire_armament(
+    world: &mut World,
+    player_tuple: &Arc<PlayerTuple<Server>>,
+    entity_index: EntityIndex,
+    index: usize,
+    aim_target: Option<Vec2>,
+    offset: Angle,
+) -> Result<(), &'static str> {
+    let entity = &mut world.entities[entity_index];
+    let data = entity.data();
+    let armament = &data.armaments[index];
+    let armament_entity_data = armament.entity_type.data();
 
-                let aim_angle = aim_target
-                    .map(|aim| Angle::from(aim - armament_entity.transform.position))
-                    .unwrap_or(entity.transform.direction);
+    let armament_transform =
+        entity.transform + data.armament_transform(&entity.extension().turrets, index);
 
-                armament_entity.guidance.velocity_target = armament_entity_data.speed;
-                armament_entity.guidance.direction_target = aim_angle;
+    if armament_entity_data.sub_kind == EntitySubKind::Depositor {
+        if let Some(mut target) = aim_target {
+            // Can't deposit in arctic.
+            target.y = target.y.min(ARCTIC - 2.0 * common::terrain::SCALE);
 
-                if armament.vertical {
-                    // Vertically-launched armaments can be launched in any horizontal direction.
-                    armament_entity.transform.direction = armament_entity.guidance.direction_target;
-                }
+            let depositor = armament_transform.position;
 
-                // Some weapons experience random deviation on launch
-                let deviation = match armament_entity_data.sub_kind {
-                    EntitySubKind::Rocket | EntitySubKind::RocketTorpedo => 0.05,
-                    EntitySubKind::Shell => 0.01,
-                    _ => 0.03,
-                };
-                armament_entity.transform.direction += thread_rng().gen::<Angle>() * deviation;
+            // Radius of depositor.
+            const MAX_RADIUS: f32 = 60.0;
 
-                if !world.spawn_here_or_nearby(armament_entity, 0.0, None) {
-                    return Err("failed to fire from current location");
-                }
-            }
+            // Max radius that will snap to MAX_RADIUS.
+            const CUTOFF_RADIUS: f32 = MAX_RADIUS * 2.0;
 
-            let entity = &mut world.entities[entity_index];
-            entity.consume_armament(index);
-            entity.extension_mut().clear_spawn_protection();
+            // Make sure target is in valid range.
+            let delta = target - depositor;
+            if delta.length_squared() > CUTOFF_RADIUS.powi(2) {
+                return Err("outside maximum range");
+            }
+            let pos = depositor + delta.clamp_length_max(MAX_RADIUS);
 
-            Ok(())
+            world.terrain.modify(TerrainMutation::simple(pos, 60.0));
         } else {
-            Err("cannot fire while not alive")
+            return Err("cannot deposit without aim target");
+        }
+    } else if armament_entity_data.sub_kind == EntitySubKind::Jammer {
+        // Jammers operate from the boat, instead of being launched.
+        entity
+            .extension_mut()
+            .start_jamming(armament_entity_data.lifespan);
+    } else {
+        // Fire weapon.
+        let mut armament_entity = Entity::new(armament.entity_type, Some(Arc::clone(player_tuple)));
+
+        armament_entity.transform = armament_transform;
+        armament_entity.altitude = entity.altitude;
+
+        // Weapons lead the locked contact, if any, instead of following the aim target.
+        let lock = entity
+            .extension()
+            .lock
+            .as_ref()
+            .filter(|_| armament_entity_data.kind == EntityKind::Weapon);
+        let aim_target = if let Some(lock) = lock {
+            Some(lead_intercept(
+                armament_entity.transform.position,
+                lock.transform.position,
+                lock.transform.velocity_vec(),
+                armament_entity_data.speed.to_mps(),
+            ))
+        } else {
+            aim_target
         };
+
+        let aim_angle = aim_target
+            .map(|aim| Angle::from(aim - armament_entity.transform.position))
+            .unwrap_or(entity.transform.direction);
+
+        armament_entity.guidance.velocity_target = armament_entity_data.speed;
+        armament_entity.guidance.direction_target = aim_angle + offset;
+
+        if armament.vertical {
+            // Vertically-launched armaments can be launched in any horizontal direction.
+            armament_entity.transform.direction = armament_entity.guidance.direction_target;
+        }
+
+        // Some weapons experience random deviation on launch
+        let deviation = match armament_entity_data.sub_kind {
+            EntitySubKind::Rocket | EntitySubKind::RocketTorpedo => 0.05,
+            EntitySubKind::Shell => 0.01,
+            _ => 0.03,
+        };
+        armament_entity.transform.direction += world.rng.gen::<Angle>() * deviation;
+
+        let id = world
+            .spawn_here_or_nearby(armament_entity, 0.0, None)
+            .ok_or("failed to fire from current location")?;
+        if armament.wire {
+            world.wires.insert(id);
+        }
     }
+
+    let entity = &mut world.entities[entity_index];
+    entity.consume_armament(index);
+    entity.extension_mut().clear_spawn_protection();
+
+    Ok(())
 }
 
 impl CommandTrait for Pay {
@@ -409,7 +740,7 @@ impl CommandTrait for Pay {
             payment.altitude = entity.altitude;
 
             // If payment successfully spawns, withdraw funds.
-            if world.spawn_here_or_nearby(payment, 1.0, None) {
+            if world.spawn_here_or_nearby(payment, 1.0, None).is_some() {
                 player.score -= withdraw;
             }
 
@@ -489,3 +820,118 @@ fn sanitize_floats<'a, F: IntoIterator<Item = &'a mut f32>>(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Entity;
+    use crate::protocol::CommandTrait;
+    use crate::world::World;
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::angle::Angle;
+    use common::entity::{EntityKind, EntityType};
+    use common::protocol::Salvo;
+    use common::terrain::{Terrain, TerrainMutation, SAND_LEVEL};
+    use common::ticks::Ticks;
+    use game_server::player::PlayerTuple;
+    use glam::Vec2;
+    use std::sync::Arc;
+
+    /// Spawns a submarine, which has several similar torpedo tubes, at the origin.
+    fn world_with_submarine(player: &Arc<PlayerTuple<Server>>) -> World {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(2000.0);
+        world.terrain = Terrain::new();
+
+        let boat = Entity::new(EntityType::Ohio, Some(Arc::clone(player)));
+        assert!(world.spawn_here_or_nearby(boat, 0.0, None).is_some());
+        world
+    }
+
+    #[test]
+    fn salvo() {
+        let player = test_bot(0);
+        let mut world = world_with_submarine(&player);
+
+        let torpedoes = |world: &World| {
+            world
+                .entities
+                .iter_radius(Vec2::ZERO, 1000.0)
+                .filter(|(_, e)| e.data().kind == EntityKind::Weapon)
+                .count()
+        };
+
+        let salvo = Salvo {
+            armament_index: 0,
+            spread: Angle::from_degrees(5.0),
+        };
+        assert!(salvo.apply(&mut world, &player).is_ok());
+
+        let index = player
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        let boat = &world.entities[index];
+        let armaments = &boat.data().armaments;
+        let similar: Vec<usize> = (0..armaments.len())
+            .filter(|&i| armaments[i].is_similar_to(&armaments[0]))
+            .collect();
+        assert!(similar.len() > 1);
+        assert_eq!(torpedoes(&world), similar.len());
+        for (i, reload) in boat.extension().reloads.iter().enumerate() {
+            assert_eq!(
+                *reload != Ticks::ZERO,
+                similar.contains(&i),
+                "armament {}",
+                i
+            );
+        }
+
+        // Nothing left to fire.
+        assert!(salvo.apply(&mut world, &player).is_err());
+        assert_eq!(torpedoes(&world), similar.len());
+    }
+
+    /// A salvo without room to launch doesn't use up any armaments.
+    #[test]
+    fn salvo_blocked() {
+        let player = test_bot(0);
+        let mut world = world_with_submarine(&player);
+
+        let index = player
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        let boat = &world.entities[index];
+        let data = boat.data();
+        let tubes: Vec<Vec2> = (0..data.armaments.len())
+            .filter(|&i| data.armaments[i].is_similar_to(&data.armaments[0]))
+            .map(|i| {
+                (boat.transform + data.armament_transform(&boat.extension().turrets, i)).position
+            })
+            .collect();
+
+        // Raise land where the torpedoes would launch.
+        for &tube in &tubes {
+            for _ in 0..10 {
+                world.terrain.modify(TerrainMutation::simple(tube, 60.0));
+            }
+            assert!(world.terrain.sample(tube).unwrap() > SAND_LEVEL);
+        }
+
+        let salvo = Salvo {
+            armament_index: 0,
+            spread: Angle::from_degrees(5.0),
+        };
+        assert!(salvo.apply(&mut world, &player).is_err());
+        let boat = &world.entities[index];
+        assert!(boat.extension().reloads.iter().all(|&r| r == Ticks::ZERO));
+    }
+}
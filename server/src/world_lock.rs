@@ -0,0 +1,54 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::entities::EntityIndex;
+use crate::entity::Entity;
+use crate::world::World;
+use common::entity::EntityKind;
+use common::transform::Transform;
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+
+impl World {
+    /// Returns true if a boat may lock on to a target, which must be a boat, aircraft, or weapon
+    /// of a non-friendly player.
+    pub(crate) fn can_lock(boat: &Entity, target: &Entity) -> bool {
+        target.player.is_some()
+            && matches!(
+                target.data().kind,
+                EntityKind::Boat | EntityKind::Aircraft | EntityKind::Weapon
+            )
+            && !target.is_friendly(boat)
+    }
+
+    /// Follows the contacts that boats are locked on to, breaking locks on contacts that are no
+    /// longer detected (or have become friendly).
+    pub fn update_locks(&mut self) {
+        let entities = &self.entities;
+
+        let updates: Vec<(EntityIndex, Option<Transform>)> = entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, boat)| boat.is_boat() && boat.extension().lock.is_some())
+            .map(|(index, boat)| {
+                let id = boat.extension().lock.as_ref().unwrap().target;
+                let transform = entities
+                    .iter_radius(boat.transform.position, boat.data().sensors.max_range())
+                    .find(|(_, target)| target.id == id)
+                    .filter(|(_, target)| {
+                        Self::can_lock(boat, target) && self.detects(boat, target)
+                    })
+                    .map(|(_, target)| target.transform);
+                (index, transform)
+            })
+            .collect();
+
+        for (index, transform) in updates {
+            let extension = self.entities[index].extension_mut();
+            if let Some(transform) = transform {
+                extension.lock.as_mut().unwrap().transform = transform;
+            } else {
+                extension.lock = None;
+            }
+        }
+    }
+}
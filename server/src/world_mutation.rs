@@ -18,7 +18,7 @@ use common::util::*;
 use common::velocity::Velocity;
 use game_server::player::PlayerTuple;
 use glam::Vec2;
-use rand::{thread_rng, Rng};
+use rand::Rng;
 use std::sync::Arc;
 
 /// Serialized mutations, targeted at an indexed entity, ordered by priority.
@@ -43,14 +43,16 @@ pub(crate) enum Mutation {
     Reload(Ticks),
     // For things that may only be collected once.
     CollectedBy(Arc<PlayerTuple<Server>>, u32),
-    HitBy(Arc<PlayerTuple<Server>>, EntityType, Ticks),
-    Attraction(Vec2, Velocity, Altitude), // Altitude is a delta.
+    HitBy(Arc<PlayerTuple<Server>>, EntityType, Ticks, Option<Vec2>), // Vec2 is where the hit landed.
+    Attraction(Vec2, Velocity, Altitude),                             // Altitude is a delta.
     Guidance {
         direction_target: Angle,
         altitude_target: Altitude,
         signal_strength: f32,
     },
     FireAll(EntitySubKind),
+    // Relaunches a rearmed aircraft from a landing pad (armament index).
+    Relaunch(usize),
 }
 
 impl Mutation {
@@ -59,7 +61,7 @@ impl Mutation {
         match self {
             Self::FireAll(_) => 127, // so that ASROC can fire before expiring
             Self::Remove(_) => 126,
-            Self::HitBy(_, _, _) => 125,
+            Self::HitBy(_, _, _, _) => 125,
             Self::CollidedWithBoat { .. } => 124,
             Self::CollectedBy(_, _) => 123,
             Self::Attraction(_, _, _) => 101,
@@ -86,7 +88,7 @@ impl Mutation {
                 signal_strength, ..
             } => -signal_strength,
             // Highest damage goes first.
-            Self::HitBy(_, _, damage) => damage.to_secs(),
+            Self::HitBy(_, _, damage, _) => damage.to_secs(),
             Self::CollidedWithBoat { damage, .. } => damage.to_secs(),
             // Closest attraction goes last (takes effect).
             Self::Attraction(delta, _, altitude) => {
@@ -120,9 +122,17 @@ impl Mutation {
                 world.remove(index, reason);
                 return true;
             }
-            Self::HitBy(other_player, weapon_type, damage) => {
+            Self::HitBy(other_player, weapon_type, damage, position) => {
                 let e = &mut entities[index];
-                if e.damage(damage) {
+                // Damage over time from fires and flooding has no position.
+                let dead = if let Some(position) = position {
+                    e.start_hazard(&other_player, weapon_type);
+                    e.damage_at(damage, position)
+                } else {
+                    e.damage(damage)
+                };
+
+                if dead {
                     let killer_alias = {
                         let mut other_player = other_player.borrow_player_mut();
                         other_player.score += kill_score(e.borrow_player().score);
@@ -221,8 +231,11 @@ impl Mutation {
             Self::FireAll(sub_kind) => {
                 let entity = &mut entities[index];
 
-                // Reset entity lifespan (because it is actively engaging in battle.
-                entity.ticks = Ticks::ZERO;
+                // Reset entity lifespan (because it is actively engaging in battle), except for
+                // aircraft fuel.
+                if entity.data().kind != EntityKind::Aircraft {
+                    entity.ticks = Ticks::ZERO;
+                }
 
                 let data = entity.data();
                 let armament_entities: Vec<Entity> = data
@@ -267,6 +280,27 @@ impl Mutation {
                     world.spawn_here_or_nearby(armament_entity, 0.0, None);
                 }
             }
+            Self::Relaunch(armament_index) => {
+                let entity = &entities[index];
+                let data = entity.data();
+                let armament = &data.armaments[armament_index];
+
+                let mut aircraft = Entity::new(
+                    armament.entity_type,
+                    Some(Arc::clone(entity.player.as_ref().unwrap())),
+                );
+                aircraft.transform = entity.transform
+                    + data.armament_transform(&entity.extension().turrets, armament_index);
+                aircraft.altitude = entity.altitude;
+                aircraft.guidance = Guidance {
+                    direction_target: aircraft.transform.direction,
+                    velocity_target: armament.entity_type.data().speed,
+                };
+
+                if world.spawn_here_or_nearby(aircraft, 0.0, None).is_some() {
+                    world.entities[index].consume_armament(armament_index);
+                }
+            }
         };
         false
     }
@@ -307,14 +341,22 @@ impl Mutation {
                 };
 
                 if let Some(boat_index) = boat_index {
-                    // Reload landed aircraft instantly on the correct pad.
+                    // Rearm landed aircraft quickly on the correct pad.
                     let landing_pad = if let DeathReason::Landing(pad) = reason {
                         Some(*pad)
                     } else {
                         None
                     };
 
-                    Self::reload_limited_armament(world, boat_index, entity_type, landing_pad)
+                    // Aircraft that returned for fuel are relaunched automatically.
+                    let relaunch = landing_pad.is_some() && world.entities[index].is_low_on_fuel();
+
+                    let reloaded =
+                        Self::reload_limited_armament(world, boat_index, entity_type, landing_pad);
+
+                    if let Some(i) = reloaded.filter(|_| relaunch) {
+                        world.entities[boat_index].extension_mut().set_relaunch(i);
+                    }
                 }
             }
         }
@@ -325,8 +367,8 @@ impl Mutation {
     /// score and the spawning of loot.
     fn boat_died(world: &mut World, index: EntityIndex, score_to_coins: bool) {
         let entity = &mut world.entities[index];
+        let rng = &mut world.rng;
         let mut player = entity.borrow_player_mut();
-        let mut rng = thread_rng();
         let score = player.score;
         player.score = if player.is_bot() {
             // Make sure there are bots in the shallow area.
@@ -336,6 +378,9 @@ impl Mutation {
         };
         drop(player);
 
+        // Release references to attackers.
+        entity.extension_mut().clear_hazards();
+
         let data = entity.data();
         debug_assert_eq!(data.kind, EntityKind::Boat);
 
@@ -346,8 +391,14 @@ impl Mutation {
         let tangent = Vec2::new(-normal.y, normal.x);
         let altitude = entity.altitude;
 
-        for loot_type in entity.entity_type.loot(score, score_to_coins) {
+        let loot: Vec<EntityType> = entity
+            .entity_type
+            .loot(rng, score, score_to_coins)
+            .collect();
+
+        for loot_type in loot {
             let mut loot_entity = Entity::new(loot_type, None);
+            let rng = &mut world.rng;
 
             // Make loot roughly conform to rectangle of ship.
             loot_entity.transform.position = center
@@ -385,7 +436,7 @@ impl Mutation {
                     let probability = (damage * (1.0 / MIN_AMOUNT)).clamp(0.0, 1.0);
                     let amount = data.damage.max(MIN_AMOUNT);
 
-                    if thread_rng().gen_bool(probability as f64) {
+                    if world.rng.gen_bool(probability as f64) {
                         // Modify terrain slightly in front of death, to account for finite tick rate.
                         // Should be more correct, on average.
                         let pos = entity.transform.position
@@ -402,14 +453,17 @@ impl Mutation {
         }
     }
 
+    /// How long it takes to refuel and rearm a landed aircraft.
+    const AIRCRAFT_TURNAROUND: Ticks = Ticks::from_whole_secs(5);
+
     /// Called by on_world_remove when a limited armament (weapon, decoy, or aircraft) dies with a
-    /// player that is alive.
+    /// player that is alive. Returns the index of the armament that was reloaded, if any.
     fn reload_limited_armament(
         world: &mut World,
         boat_index: EntityIndex,
         entity_type: EntityType,
         landing_pad: Option<usize>,
-    ) {
+    ) -> Option<usize> {
         let armament_data: &EntityData = entity_type.data();
 
         // Only call this on limited armaments.
@@ -426,7 +480,7 @@ impl Mutation {
             debug_assert_eq!(a.entity_type, entity_type);
             if *c == Ticks::MAX {
                 *c = if landing_pad.is_some() {
-                    Ticks::ZERO
+                    Self::AIRCRAFT_TURNAROUND
                 } else {
                     a.reload()
                 };
@@ -442,17 +496,18 @@ impl Mutation {
 
         if let Some(i) = landing_pad {
             if try_reload(&armaments[i], &mut consumption[i]) {
-                return;
+                return Some(i);
             }
         }
 
-        for (a, c) in armaments
+        for (i, (a, c)) in armaments
             .iter()
             .zip(consumption.iter_mut())
-            .filter(|(a, _)| a.entity_type == entity_type)
+            .enumerate()
+            .filter(|(_, (a, _))| a.entity_type == entity_type)
         {
             if try_reload(a, c) {
-                return;
+                return Some(i);
             }
         }
 
@@ -468,5 +523,6 @@ impl Mutation {
             "failed to reload limited armament for {:?} {:?}", boat.entity_type, boat.extension().spawn_protection()
         );
          */
+        None
     }
 }
@@ -7,13 +7,371 @@ use crate::entity::Entity;
 use crate::player::Status;
 use crate::server::Server;
 use crate::world::World;
+use common::altitude::Altitude;
+use common::contact::ContactTrait;
 use common::entity::{EntityKind, EntitySubKind};
+use common::terrain::Terrain;
 use common::ticks::Ticks;
-use common_util::range::{map_ranges, map_ranges_fast};
+use common::weather::Weather;
+use common_util::range::{gen_radius, map_ranges, map_ranges_fast};
+use core_protocol::id::PlayerId;
 use game_server::player::PlayerTuple;
 use glam::{vec2, Vec2};
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+use rand::Rng;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Players, whether alive or dead, can see other entities based on these parameters.
+struct Camera {
+    active: bool,
+    /// Whether the camera is above the terrain, so it isn't blocked by it.
+    airborne: bool,
+    inner: f32,
+    position: Vec2,
+    radar: f32,
+    sonar: f32,
+    speed: f32,
+    view: f32,
+    visual: f32,
+}
+
+/// Sensor parameters of a camera, precomputed to efficiently detect many entities.
+struct Detector {
+    active: bool,
+    airborne: bool,
+    position: Vec2,
+    speed: f32,
+    max_range_squared: f32,
+    radar_range_inv: f32,
+    sonar_range_inv: f32,
+    visual_range_inv: f32,
+}
+
+impl Detector {
+    /// How much jamming increases radar uncertainty.
+    const JAMMING_FACTOR: f32 = 4.0;
+
+    fn new(camera: &Camera) -> Self {
+        Self {
+            active: camera.active,
+            airborne: camera.airborne,
+            position: camera.position,
+            speed: camera.speed,
+            max_range_squared: camera.visual.max(camera.radar.max(camera.sonar)).powi(2),
+            radar_range_inv: camera.radar.powi(-2),
+            sonar_range_inv: camera.sonar.powi(-2),
+            visual_range_inv: camera.visual.powi(-2),
+        }
+    }
+
+    /// Returns whether an entity is visible, and the uncertainty with which it is detected (1.0 or
+    /// more if it isn't detected at all). Visual and radar require line of sight, unless either end
+    /// is airborne (and therefore above the terrain).
+    fn detect(
+        &self,
+        entity: &Entity,
+        distance_squared: f32,
+        mut line_of_sight: impl FnMut() -> bool,
+    ) -> (bool, f32) {
+        let data = entity.data();
+        let altitude = entity.altitude;
+        let airborne = self.airborne || altitude.is_airborne();
+        let mut line_of_sight = || airborne || line_of_sight();
+        let inv_size = data.inv_size;
+        let default_ratio = distance_squared * inv_size;
+        let mut visible = false;
+        let mut uncertainty = 1f32;
+        let entity_abs_vel = entity.transform.velocity.abs().to_mps();
+
+        if self.radar_range_inv.is_finite() && !altitude.is_submerged() && line_of_sight() {
+            let mut radar_ratio = default_ratio * self.radar_range_inv;
+
+            if data.kind == EntityKind::Boat && entity.extension().is_jamming() {
+                // Jamming hides the boat amongst false returns.
+                radar_ratio *= Self::JAMMING_FACTOR;
+            }
+
+            if self.active {
+                // Active radar can see moving targets easier.
+                uncertainty = uncertainty.min(radar_ratio * 15.0 / (15.0 + entity_abs_vel));
+            }
+
+            // Always-on passive radar:
+            // Inlined to allow constant propagation and replace div with mul.
+            const BASE_FACTOR: f32 = 25.0;
+            const BASE_EMISSION: f32 = 5.0f32;
+            // let mut emission = BASE_EMISSION;
+            let passive_radar_ratio = if data.kind == EntityKind::Boat {
+                const BOAT_EMISSION: f32 = 5.0;
+                // emission += BOAT_EMISSION;
+                if entity.extension().is_active() && data.sensors.radar.range > 0.0 {
+                    // Active radar gives away entity's position.
+                    const ACTIVE_EMISSION: f32 = 20.0;
+                    // emission += ACTIVE_EMISSION;
+                    BASE_FACTOR / (BASE_EMISSION + BOAT_EMISSION + ACTIVE_EMISSION)
+                } else {
+                    BASE_FACTOR / (BASE_EMISSION + BOAT_EMISSION)
+                }
+            } else if data.sub_kind == EntitySubKind::Missile {
+                const MISSILE_EMISSION: f32 = 30.0;
+                // emission += MISSILE_EMISSION;
+                BASE_FACTOR / (BASE_EMISSION + MISSILE_EMISSION)
+            } else {
+                BASE_FACTOR / BASE_EMISSION
+            };
+            // let passive_radar_ratio = BASE_FACTOR / emission;
+
+            uncertainty = uncertainty.min(passive_radar_ratio);
+        }
+
+        if self.sonar_range_inv.is_finite() && !altitude.is_airborne() {
+            let mut sonar_ratio = default_ratio * self.sonar_range_inv;
+            if self.active {
+                // Active sonar.
+                uncertainty = uncertainty.min(sonar_ratio);
+            }
+
+            // Beyond this point, sonar_ratio means passive sonar ratio.
+
+            // Always-on passive sonar:
+            let mut noise =
+                2f32.max(entity_abs_vel - data.cavitation_speed(entity.altitude).to_mps());
+
+            if data.kind == EntityKind::Boat
+                || data.kind == EntityKind::Weapon
+                || data.kind == EntityKind::Decoy
+            {
+                noise *= 2.0;
+
+                if data.kind != EntityKind::Boat {
+                    noise += 100.0;
+                } else if entity.extension().is_active() && data.sensors.sonar.range > 0.0 {
+                    // Active sonar gives away entity's position.
+                    noise += 20.0;
+                }
+            }
+
+            if data.kind == EntityKind::Boat && altitude.is_submerged() {
+                // Shallow submarines disturb the surface, so are easier to hear than deep ones.
+                noise *= map_ranges(altitude.to_norm(), 0.0..-0.5, 1.5..1.0, true);
+            }
+
+            sonar_ratio /= noise;
+
+            // Making noise of your own reduces the performance of
+            // passive sonar
+            sonar_ratio *= 20.0 + self.speed;
+            uncertainty = uncertainty.min(sonar_ratio);
+        }
+
+        if self.visual_range_inv.is_finite() && line_of_sight() {
+            let mut visual_ratio = default_ratio * self.visual_range_inv;
+            if altitude.is_submerged() {
+                let extra = if data.kind == EntityKind::Boat
+                    && entity.extension().reloads.iter().any(|&t| t > Ticks::ZERO)
+                {
+                    // A submarine that has fired recently is visible, for practical reasons.
+                    0.05
+                } else {
+                    0.0
+                };
+                // Don't clamp high because to_norm can't return above 1.0 (high).
+                visual_ratio /=
+                    map_ranges_fast(altitude.to_norm(), -0.5..1.0, 0.0..0.8, true, false) + extra;
+            }
+            visible = visual_ratio < 1.0;
+            uncertainty = uncertainty.min(visual_ratio);
+        }
+
+        (visible, uncertainty)
+    }
+}
+
+/// The sensors of a boat, sonobuoy, or dipping sonar, whose detections are shared with its
+/// player's teammates (data-link).
+pub(crate) struct Sensor {
+    player: Arc<PlayerTuple<Server>>,
+    /// False for sonobuoys and dipping sonars.
+    boat: bool,
+    detector: Detector,
+}
+
+/// Detects an entity with teammates' sensors, returning a reduced fidelity contact if any of them
+/// detected it.
+fn detect_shared<'a>(
+    teammates: &[(PlayerId, &Detector)],
+    entity: &'a Entity,
+    terrain: &Terrain,
+    friendly: bool,
+) -> Option<ContactRef<'a>> {
+    // Share the best detection.
+    let (spotter, visible, uncertainty) = teammates
+        .iter()
+        .filter_map(|(spotter, detector)| {
+            let distance_squared = detector
+                .position
+                .distance_squared(entity.transform.position);
+            if distance_squared > detector.max_range_squared {
+                return None;
+            }
+            let mut line_of_sight_cache = None;
+            let (visible, uncertainty) = detector.detect(entity, distance_squared, || {
+                *line_of_sight_cache.get_or_insert_with(|| {
+                    terrain.line_of_sight(detector.position, entity.transform.position)
+                })
+            });
+            (uncertainty < 1.0).then(|| (*spotter, visible, uncertainty))
+        })
+        .min_by(|a, b| a.2.partial_cmp(&b.2).unwrap())?;
+
+    let has_type = entity.data().kind == EntityKind::Collectible || friendly || uncertainty < 0.5;
+
+    if !has_type && !visible && entity.altitude.is_submerged() {
+        // Weak passive sonar contacts are only meaningful relative to whoever detected them.
+        return None;
+    }
+
+    Some(ContactRef::new(entity, visible, false, has_type).with_spotter(spotter))
+}
 
 impl World {
+    /// Radius of uncertainty of weak passive sonar contacts, relative to their distance and
+    /// uncertainty.
+    const SONAR_ERROR: f32 = 0.3;
+    /// Radius of uncertainty of jamming contacts, relative to their distance.
+    const JAMMING_ERROR: f32 = 0.5;
+    /// Radius of uncertainty of contacts only detected by their radar emissions, relative to their
+    /// distance.
+    const EMISSION_ERROR: f32 = 0.3;
+    /// Fraction of visual range that remains at periscope depth.
+    const PERISCOPE_VISUAL: f32 = 0.5;
+    /// Speed (in meters per second) below which helicopters lower their dipping sonar.
+    const DIPPING_SPEED: f32 = 3.0;
+
+    /// Returns the camera of a boat, based on its sensors and the surrounding conditions.
+    fn boat_camera(&self, entity: &Entity) -> Camera {
+        let data = entity.data();
+        let sensors = &data.sensors;
+
+        // Radar and visual don't work under water, except for a periscope just below the surface.
+        let (visual_efficacy, radar_efficacy) = if !entity.altitude.is_submerged() {
+            (1.0, 1.0)
+        } else if entity.altitude >= Altitude::PERISCOPE_DEPTH {
+            (Self::PERISCOPE_VISUAL, 0.0)
+        } else {
+            (0.0, 0.0)
+        };
+
+        let weather = Weather::sample(&self.weather, entity.transform.position);
+        let sensor_factor = entity.extension().sensor_factor(data);
+
+        let visual = sensors.visual.range
+            * sensor_factor
+            * visual_efficacy
+            * weather.visual_factor
+            * self.time_of_day.visual_factor();
+        let radar = sensors.radar.range * sensor_factor * radar_efficacy * weather.radar_factor;
+
+        // Sonar works at full effective range as long as it is not airborne.
+        let sonar = if entity.altitude.is_airborne() {
+            0.0
+        } else {
+            sensors.sonar.range * sensor_factor
+        };
+
+        Camera {
+            active: entity.extension().is_active(),
+            airborne: entity.altitude.is_airborne(),
+            inner: data.radii().start,
+            position: entity.transform.position,
+            radar,
+            sonar,
+            speed: entity.transform.velocity.abs().to_mps(),
+            view: data.camera_range(),
+            visual,
+        }
+    }
+
+    /// Returns the camera of a sonobuoy, or of a hovering helicopter's dipping sonar, which only
+    /// have sonar. Returns None for all other entities.
+    fn sonar_camera(entity: &Entity) -> Option<Camera> {
+        let data = entity.data();
+        let active = match data.sub_kind {
+            EntitySubKind::Sonobuoy => false,
+            EntitySubKind::Heli
+                if entity.transform.velocity.abs().to_mps() < Self::DIPPING_SPEED =>
+            {
+                true
+            }
+            _ => return None,
+        };
+
+        let sonar = data.sensors.sonar.range;
+        (sonar > 0.0).then(|| Camera {
+            active,
+            airborne: false,
+            inner: 0.0,
+            position: entity.transform.position,
+            radar: 0.0,
+            sonar,
+            speed: 0.0,
+            view: 0.0,
+            visual: 0.0,
+        })
+    }
+
+    /// Gathers the sensors of all boats, sonobuoys, and dipping sonars that belong to a player.
+    pub(crate) fn gather_sensors(&self) -> Vec<Sensor> {
+        let entities: Vec<&Entity> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .map(|(_, e)| e)
+            .filter(|e| {
+                e.player.is_some()
+                    && (e.is_boat()
+                        || matches!(
+                            e.data().sub_kind,
+                            EntitySubKind::Sonobuoy | EntitySubKind::Heli
+                        ))
+            })
+            .collect();
+
+        entities
+            .into_iter()
+            .filter_map(|e| {
+                let camera = if e.is_boat() {
+                    self.boat_camera(e)
+                } else {
+                    Self::sonar_camera(e)?
+                };
+                Some(Sensor {
+                    player: Arc::clone(e.player.as_ref().unwrap()),
+                    boat: e.is_boat(),
+                    detector: Detector::new(&camera),
+                })
+            })
+            .collect()
+    }
+
+    /// Returns true if a boat's own sensors detect a target well enough to lock on to it, meaning
+    /// it is visible or identified.
+    pub(crate) fn detects(&self, boat: &Entity, target: &Entity) -> bool {
+        let detector = Detector::new(&self.boat_camera(boat));
+        let distance_squared = detector
+            .position
+            .distance_squared(target.transform.position);
+        if distance_squared > detector.max_range_squared {
+            return false;
+        }
+        let (visible, uncertainty) = detector.detect(target, distance_squared, || {
+            self.terrain
+                .line_of_sight(detector.position, target.transform.position)
+        });
+        visible || uncertainty < 0.5
+    }
+
     /// get_player_complete gets the complete update for a player, corresponding to everything they
     /// are able to see at the current moment.
     pub fn get_player_complete<'a>(
@@ -30,52 +388,8 @@ impl World {
             _ => None,
         };
 
-        struct Camera {
-            active: bool,
-            inner: f32,
-            position: Vec2,
-            radar: f32,
-            sonar: f32,
-            speed: f32,
-            view: f32,
-            visual: f32,
-        }
-
-        // Players, whether alive or dead, can see other entities based on these parameters.
         let camera = if let Some(entity) = player_entity {
-            let data = entity.data();
-            let sensors = &data.sensors;
-
-            // Ranges from -1.0 to 1.0 where 0.0 is sea level.
-            let norm_altitude = entity.altitude.to_norm();
-
-            // Radar and visual don't work well under water.
-            let visual_radar_efficacy = map_ranges(norm_altitude, -0.35..0.0, 0.0..1.0, true);
-
-            let visual = sensors.visual.range * visual_radar_efficacy;
-            let radar = sensors.radar.range * visual_radar_efficacy;
-
-            // Sonar works at full effective range as long as it is not airborne.
-            let sonar = if entity.altitude.is_airborne() {
-                0.0
-            } else {
-                sensors.sonar.range
-            };
-
-            if player.data.status.is_alive() {
-                Camera {
-                    active: entity.extension().is_active(),
-                    inner: data.radii().start,
-                    position: entity.transform.position,
-                    radar,
-                    sonar,
-                    speed: entity.transform.velocity.abs().to_mps(),
-                    view: data.camera_range(),
-                    visual,
-                }
-            } else {
-                panic!("player not alive in outbound");
-            }
+            self.boat_camera(entity)
         } else if let Status::Dead {
             position,
             time,
@@ -88,6 +402,7 @@ impl World {
             let range = map_ranges(elapsed, 10.0..2.0, 0.0..visual_range, true).max(500.0);
             Camera {
                 active: true,
+                airborne: false,
                 inner: 0.0,
                 position,
                 radar: range,
@@ -100,6 +415,7 @@ impl World {
             let range = 500.0;
             Camera {
                 active: true,
+                airborne: false,
                 inner: 0.0,
                 position: Vec2::ZERO,
                 radar: range,
@@ -110,146 +426,159 @@ impl World {
             }
         };
 
-        let visual_range_inv = camera.visual.powi(-2);
-        let radar_range_inv = camera.radar.powi(-2);
-        let sonar_range_inv = camera.sonar.powi(-2);
+        // Sonobuoys and dipping sonars listen on behalf of their owner, no matter how far away they
+        // are.
+        let sonars: Vec<&Detector> = self
+            .sensors
+            .iter()
+            .filter(|sensor| !sensor.boat && &*sensor.player == tuple)
+            .map(|sensor| &sensor.detector)
+            .collect();
+
+        // Teammates share what their sensors (including sonobuoys and dipping sonars) detect
+        // (data-link), no matter how far away they are.
+        let teammates: Vec<(PlayerId, &Detector)> = if let Some(team_id) = player.team_id() {
+            self.sensors
+                .iter()
+                .filter(|sensor| {
+                    &*sensor.player != tuple
+                        && sensor.player.borrow_player().team_id() == Some(team_id)
+                })
+                .map(|sensor| (sensor.player.borrow_player().player_id, &sensor.detector))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        let detector = Detector::new(&camera);
         let max_range = camera.visual.max(camera.radar.max(camera.sonar));
+        let max_range_squared = max_range.powi(2);
+        // Active radar stands out at night, so it can be detected beyond the usual ranges.
+        let emission_range = camera.radar * self.time_of_day.emission_factor();
+        let search_range = max_range.max(emission_range);
         let close_proximity_squared = player_entity.map_or(0.0, |e| {
             (e.entity_type.data().radius + Entity::CLOSE_PROXIMITY).powi(2)
         });
         let inner_circle_squared = camera.inner.powi(2);
         let camera_pos = camera.position;
         let camera_view = camera.view;
+        let terrain = &self.terrain;
+        let jitter_seed = self.seed ^ player.player_id.0.get() as u64;
+
+        // Teammates and sonars may detect entities beyond the search range.
+        let search_range_squared = search_range.powi(2);
+        let mut remote_ids = HashSet::new();
+        let remote: Vec<&Entity> = teammates
+            .iter()
+            .map(|(_, detector)| *detector)
+            .chain(sonars.iter().copied())
+            .flat_map(|detector| {
+                self.entities
+                    .iter_radius(detector.position, detector.max_range_squared.sqrt())
+                    .map(|(_, e)| e)
+            })
+            .filter(|e| {
+                camera_pos.distance_squared(e.transform.position) > search_range_squared
+                    && remote_ids.insert(e.id)
+            })
+            .collect();
 
         let contacts = player_entity
             .into_iter()
             .chain(
                 self.entities
-                    .iter_radius(camera.position, max_range)
+                    .iter_radius(camera.position, search_range)
                     .map(|(_, e)| e)
                     .filter(move |e| Some(*e) != player_entity),
             )
+            .chain(remote)
             .filter_map(move |entity| {
                 // Limit contacts based on visibility.
 
                 let data = entity.data();
 
                 // Variables related to the relationship between the player and the contact.
-                let distance_squared = camera.position.distance_squared(entity.transform.position);
+                let distance_squared = camera_pos.distance_squared(entity.transform.position);
                 let same_player =
                     entity.player.is_some() && tuple == &**entity.player.as_ref().unwrap();
                 let friendly = entity.is_friendly_to_player(Some(tuple));
                 let known = same_player || (friendly && distance_squared < 800f32.powi(2));
 
+                // Visual and radar are blocked by land (unlike sonar). Computed lazily, since
+                // marching the terrain is relatively expensive.
+                let mut line_of_sight_cache = None;
+                let mut line_of_sight = || {
+                    *line_of_sight_cache.get_or_insert_with(|| {
+                        terrain.line_of_sight(camera_pos, entity.transform.position)
+                    })
+                };
+
+                // Reveals the bearing, but not the range (within a fraction of the distance), of
+                // an emitter.
+                let bearing_only = |range_error: f32| {
+                    let distance = distance_squared.sqrt();
+                    let radius = distance * range_error;
+                    let error = Self::keyed_rng(jitter_seed, entity.id.get() as u64)
+                        .gen_range(-radius..=radius);
+                    let bearing = (entity.transform.position - camera_pos).normalize_or_zero();
+                    ContactRef::new(entity, false, false, false)
+                        .with_estimate(entity.transform.position + bearing * error, radius)
+                };
+
+                // Jamming reveals the bearing, but not the range, of the jammer.
+                let jamming = || {
+                    (data.kind == EntityKind::Boat
+                        && entity.extension().is_jamming()
+                        && !entity.altitude.is_submerged())
+                    .then(|| bearing_only(Self::JAMMING_ERROR))
+                };
+
+                // Sonobuoys and dipping sonars improve on the player's own sensors.
+                let mut heard_cache = None;
+                let mut heard = || {
+                    *heard_cache.get_or_insert_with(|| {
+                        sonars
+                            .iter()
+                            .filter_map(|sonar| {
+                                let distance_squared =
+                                    sonar.position.distance_squared(entity.transform.position);
+                                (distance_squared <= sonar.max_range_squared).then(|| {
+                                    // Sonar doesn't require line of sight.
+                                    sonar.detect(entity, distance_squared, || false).1
+                                })
+                            })
+                            .fold(1.0, f32::min)
+                    })
+                };
+
+                // Falls back to what teammates detected.
+                let shared = || detect_shared(&teammates, entity, terrain, friendly);
+
+                if !known && distance_squared > max_range_squared && heard() >= 1.0 {
+                    // Only detectable by passive radar, and only if emitting.
+                    return shared()
+                        .or_else(|| {
+                            (!entity.altitude.is_submerged()
+                                && data.kind == EntityKind::Boat
+                                && entity.extension().is_active()
+                                && data.sensors.radar.range > 0.0
+                                && line_of_sight())
+                            .then(|| bearing_only(Self::EMISSION_ERROR))
+                        })
+                        .or_else(jamming);
+                }
+
                 // Variables related to detecting the contact.
-                let mut visible = false;
-                let mut uncertainty = 0f32;
                 let altitude = entity.altitude;
+                let (visible, mut uncertainty) = if known {
+                    (false, 0.0)
+                } else {
+                    let (visible, uncertainty) =
+                        detector.detect(entity, distance_squared, &mut line_of_sight);
+                    (visible, uncertainty.min(heard()))
+                };
 
                 if !known {
-                    let inv_size = data.inv_size;
-                    let default_ratio = distance_squared * inv_size;
-                    uncertainty = 1.0;
-                    let entity_abs_vel = entity.transform.velocity.abs().to_mps();
-
-                    if radar_range_inv.is_finite() && !altitude.is_submerged() {
-                        let radar_ratio = default_ratio * radar_range_inv;
-
-                        if camera.active {
-                            // Active radar can see moving targets easier.
-                            uncertainty =
-                                uncertainty.min(radar_ratio * 15.0 / (15.0 + entity_abs_vel));
-                        }
-
-                        // Always-on passive radar:
-                        // Inlined to allow constant propagation and replace div with mul.
-                        const BASE_FACTOR: f32 = 25.0;
-                        const BASE_EMISSION: f32 = 5.0f32;
-                        // let mut emission = BASE_EMISSION;
-                        let passive_radar_ratio = if data.kind == EntityKind::Boat {
-                            const BOAT_EMISSION: f32 = 5.0;
-                            // emission += BOAT_EMISSION;
-                            if entity.extension().is_active() && data.sensors.radar.range > 0.0 {
-                                // Active radar gives away entity's position.
-                                const ACTIVE_EMISSION: f32 = 20.0;
-                                // emission += ACTIVE_EMISSION;
-                                BASE_FACTOR / (BASE_EMISSION + BOAT_EMISSION + ACTIVE_EMISSION)
-                            } else {
-                                BASE_FACTOR / (BASE_EMISSION + BOAT_EMISSION)
-                            }
-                        } else if data.sub_kind == EntitySubKind::Missile {
-                            const MISSILE_EMISSION: f32 = 30.0;
-                            // emission += MISSILE_EMISSION;
-                            BASE_FACTOR / (BASE_EMISSION + MISSILE_EMISSION)
-                        } else {
-                            BASE_FACTOR / BASE_EMISSION
-                        };
-                        // let passive_radar_ratio = BASE_FACTOR / emission;
-
-                        uncertainty = uncertainty.min(passive_radar_ratio);
-                    }
-
-                    if sonar_range_inv.is_finite() && !altitude.is_airborne() {
-                        let mut sonar_ratio = default_ratio * sonar_range_inv;
-                        if camera.active {
-                            // Active sonar.
-                            uncertainty = uncertainty.min(sonar_ratio);
-                        }
-
-                        // Beyond this point, sonar_ratio means passive sonar ratio.
-
-                        // Always-on passive sonar:
-                        let mut noise = 2f32
-                            .max(entity_abs_vel - data.cavitation_speed(entity.altitude).to_mps());
-
-                        if data.kind == EntityKind::Boat
-                            || data.kind == EntityKind::Weapon
-                            || data.kind == EntityKind::Decoy
-                        {
-                            noise *= 2.0;
-
-                            if data.kind != EntityKind::Boat {
-                                noise += 100.0;
-                            } else if entity.extension().is_active()
-                                && data.sensors.sonar.range > 0.0
-                            {
-                                // Active sonar gives away entity's position.
-                                noise += 20.0;
-                            }
-                        }
-
-                        sonar_ratio /= noise;
-
-                        // Making noise of your own reduces the performance of
-                        // passive sonar
-                        sonar_ratio *= 20.0 + camera.speed;
-                        uncertainty = uncertainty.min(sonar_ratio);
-                    }
-
-                    if visual_range_inv.is_finite() {
-                        let mut visual_ratio = default_ratio * visual_range_inv;
-                        if altitude.is_submerged() {
-                            let extra = if data.kind == EntityKind::Boat
-                                && entity.extension().reloads.iter().any(|&t| t > Ticks::ZERO)
-                            {
-                                // A submarine that has fired recently is visible, for practical reasons.
-                                0.05
-                            } else {
-                                0.0
-                            };
-                            // Don't clamp high because to_norm can't return above 1.0 (high).
-                            visual_ratio /= map_ranges_fast(
-                                altitude.to_norm(),
-                                -0.5..1.0,
-                                0.0..0.8,
-                                true,
-                                false,
-                            ) + extra;
-                        }
-                        visible = visual_ratio < 1.0;
-                        uncertainty = uncertainty.min(visual_ratio);
-                    }
-
                     if player_entity.is_some()
                         && data.kind == EntityKind::Weapon
                         && distance_squared < close_proximity_squared // Do faster check first.
@@ -265,8 +594,9 @@ impl World {
 
                     if uncertainty >= 1.0 {
                         // This player has no knowledge of this entity,
-                        // so it is not a contact.
-                        return None;
+                        // so it is not a contact (unless a teammate detected it, or it is
+                        // jamming).
+                        return shared().or_else(jamming);
                     }
                 }
 
@@ -275,7 +605,27 @@ impl World {
                     || uncertainty < 0.5
                     || distance_squared < inner_circle_squared;
 
-                Some(ContactRef::new(entity, visible, known, has_type))
+                if !has_type {
+                    // A teammate may have identified the contact.
+                    if let Some(contact) = shared().filter(|c| c.entity_type().is_some()) {
+                        return Some(contact);
+                    }
+                }
+
+                let contact = ContactRef::new(entity, visible, known, has_type);
+
+                if !has_type && !visible && altitude.is_submerged() {
+                    // Weakly detected by passive sonar, so only send an approximate position. The
+                    // error is consistent for each observer, so it can't be averaged away.
+                    let radius = distance_squared.sqrt() * uncertainty * Self::SONAR_ERROR;
+                    let error = gen_radius(
+                        &mut Self::keyed_rng(jitter_seed, entity.id.get() as u64),
+                        radius,
+                    );
+                    return Some(contact.with_estimate(entity.transform.position + error, radius));
+                }
+
+                Some(contact)
             });
 
         // How much more terrain can be sent.
@@ -292,3 +642,258 @@ impl World {
         CompleteRef::new(contacts, player, self, camera_pos, camera_dims)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Entity;
+    use crate::world::World;
+    use crate::world_outbound::{detect_shared, Camera, Detector};
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::angle::Angle;
+    use common::complete::CompleteTrait;
+    use common::contact::ContactTrait;
+    use common::entity::EntityType;
+    use common::terrain::Terrain;
+    use common::ticks::Ticks;
+    use common::time_of_day::TimeOfDay;
+    use core_protocol::id::TeamId;
+    use game_server::player::PlayerTuple;
+    use glam::{vec2, Vec2};
+    use std::num::NonZeroU32;
+    use std::sync::Arc;
+
+    /// A teammate's view of an enemy boat is shared without the parts only the teammate knows.
+    #[test]
+    fn shared_boat() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(2000.0);
+        world.terrain = Terrain::new();
+
+        let players: Vec<_> = [vec2(0.0, 0.0), vec2(300.0, 0.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| {
+                let player = test_bot(i);
+                let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&player)));
+                entity.transform.position = position;
+                assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+                player
+            })
+            .collect();
+
+        let index = |player: &Arc<PlayerTuple<Server>>| {
+            player
+                .borrow_player()
+                .data
+                .status
+                .get_entity_index()
+                .unwrap()
+        };
+        // The enemy's turrets are aimed away from their defaults.
+        for angle in world.entities[index(&players[1])]
+            .extension_mut()
+            .turrets_mut()
+        {
+            *angle = *angle + Angle::from_degrees(30.0);
+        }
+
+        let teammate = &world.entities[index(&players[0])];
+        let enemy = &world.entities[index(&players[1])];
+        let spotter = teammate.borrow_player().player_id;
+
+        let detector = Detector::new(&world.boat_camera(teammate));
+        let contact = detect_shared(&[(spotter, &detector)], enemy, &world.terrain, false)
+            .expect("teammate should detect nearby boat");
+        assert_eq!(contact.spotter(), Some(spotter));
+        assert_eq!(contact.entity_type(), Some(EntityType::Yamato));
+        assert!(!contact.reloads_known());
+        assert!(contact.turrets_known());
+        let default_turrets: Vec<Angle> = enemy.data().turrets.iter().map(|t| t.angle).collect();
+        assert_eq!(contact.turrets(), default_turrets.as_slice());
+        assert_ne!(contact.turrets(), &*enemy.extension().turrets);
+    }
+
+    /// Teammates share what they detect no matter how far away from the player they are.
+    #[test]
+    fn shared_far() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(20000.0);
+        world.terrain = Terrain::new();
+
+        let players: Vec<_> = [vec2(0.0, 0.0), vec2(8000.0, 0.0), vec2(8300.0, 0.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, &position)| {
+                let player = test_bot(i);
+                let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&player)));
+                entity.transform.position = position;
+                assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+                player
+            })
+            .collect();
+
+        let team_id = Some(TeamId(NonZeroU32::new(1).unwrap()));
+        for player in &players[0..2] {
+            player
+                .borrow_player_mut()
+                .set_team_id_outside_arena(team_id);
+        }
+        world.sensors = world.gather_sensors();
+
+        let enemy_id = players[2].borrow_player().player_id;
+        let spotter = players[1].borrow_player().player_id;
+        let contacts = world.get_player_complete(&players[0]).collect_contacts();
+        let contact = contacts
+            .iter()
+            .find(|c| c.player_id() == Some(enemy_id))
+            .expect("teammate should share distant enemy");
+        assert_eq!(contact.spotter(), Some(spotter));
+
+        // Players must be solo when dropped.
+        for player in &players {
+            player.borrow_player_mut().set_team_id_outside_arena(None);
+        }
+    }
+
+    /// Sonobuoys and dipping sonars listen on behalf of their owner, no matter how far away they
+    /// are.
+    #[test]
+    fn sonars_far() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(20000.0);
+        world.terrain = Terrain::new();
+
+        let player = test_bot(0);
+        let entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&player)));
+        assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+
+        // A sonobuoy, and a hovering helicopter with a dipping sonar, each near an enemy.
+        let enemies: Vec<_> = [
+            (EntityType::Ssq53, vec2(8000.0, 0.0)),
+            (EntityType::Seahawk, vec2(-8000.0, 0.0)),
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, &(sonar_type, position))| {
+            let enemy = test_bot(i + 1);
+            let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&enemy)));
+            entity.transform.position = position + vec2(0.0, 300.0);
+            assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+
+            let mut sonar = Entity::new(sonar_type, Some(Arc::clone(&player)));
+            sonar.transform.position = position;
+            assert!(world.spawn_here_or_nearby(sonar, 0.0, None).is_some());
+            enemy
+        })
+        .collect();
+        world.sensors = world.gather_sensors();
+
+        let contacts = world.get_player_complete(&player).collect_contacts();
+        for enemy in &enemies {
+            let enemy_id = enemy.borrow_player().player_id;
+            assert!(
+                contacts.iter().any(|c| c.player_id() == Some(enemy_id)),
+                "{:?} should be heard",
+                enemy_id
+            );
+        }
+    }
+
+    /// Jamming hides a boat amongst false radar returns.
+    #[test]
+    fn jamming_uncertainty() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut boat = Entity::new(EntityType::Yamato, Some(test_bot(0)));
+        boat.transform.position = vec2(300.0, 0.0);
+        // Passive, so its own radar emissions don't give it away.
+        boat.extension_mut().set_active(false);
+        boat.extension_mut()
+            .update_tickers(Ticks::from_whole_secs(1));
+
+        // Only radar.
+        let detector = Detector::new(&Camera {
+            active: true,
+            airborne: false,
+            inner: 0.0,
+            position: Vec2::ZERO,
+            radar: 2000.0,
+            sonar: 0.0,
+            speed: 0.0,
+            view: 2000.0,
+            visual: 0.0,
+        });
+        let uncertainty = |boat: &Entity| detector.detect(boat, 300f32.powi(2), || true).1;
+
+        let clear = uncertainty(&boat);
+        boat.extension_mut()
+            .start_jamming(Ticks::from_whole_secs(10));
+        let jammed = uncertainty(&boat);
+        assert!(clear < 1.0 / Detector::JAMMING_FACTOR);
+        assert!((jammed - clear * Detector::JAMMING_FACTOR).abs() < 0.001);
+    }
+
+    /// A jamming boat beyond sensor range reveals its bearing, but not its range.
+    #[test]
+    fn jamming_bearing() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+        // Emissions can be detected furthest at midnight.
+        world.time_of_day = TimeOfDay::new(0.0);
+
+        let player = test_bot(0);
+        let entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&player)));
+        assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+        let index = player
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        let camera = world.boat_camera(&world.entities[index]);
+        let distance = camera.radar * 1.5;
+        assert!(distance > camera.visual.max(camera.sonar));
+
+        let jammer = test_bot(1);
+        let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&jammer)));
+        entity.transform.position = vec2(distance, 0.0);
+        let jammer_id = world.spawn_here_or_nearby(entity, 0.0, None).unwrap();
+        let index = jammer
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        let extension = world.entities[index].extension_mut();
+        // Passive, so not detectable by its radar emissions.
+        extension.set_active(false);
+        extension.update_tickers(Ticks::from_whole_secs(1));
+        extension.start_jamming(Ticks::from_whole_secs(10));
+
+        let contacts = world.get_player_complete(&player).collect_contacts();
+        let contact = contacts
+            .iter()
+            .find(|c| c.id() == jammer_id)
+            .expect("jammer should be a contact");
+        assert_eq!(contact.entity_type(), None);
+        let (position, radius) = contact.estimate().expect("only an estimate");
+        assert!((radius - distance * World::JAMMING_ERROR).abs() < 0.001);
+        assert!(Angle::from(position).abs() < Angle::from_degrees(1.0));
+    }
+}
@@ -2,16 +2,20 @@
 // SPDX-License-Identifier: AGPL-3.0-or-later
 
 use crate::entities::EntityIndex;
+use crate::entity::{Entity, Launcher};
 use crate::player::{Flags, Status};
 use crate::world::World;
+use crate::world_mutation::Mutation;
 use common::altitude::Altitude;
 use common::angle::Angle;
 use common::death_reason::DeathReason;
 use common::entity::*;
+use common::protocol::SquadronOrder;
 use common::terrain::TerrainMutation;
 use common::ticks::Ticks;
 use common::transform::Transform;
 use common::velocity::Velocity;
+use common::weather::Weather;
 use common::world::{
     clamp_y_to_strict_area_border, outside_strict_area, strict_area_border_normal, ARCTIC,
 };
@@ -19,10 +23,12 @@ use common_util::range::map_ranges;
 use glam::Vec2;
 use maybe_parallel_iterator::{IntoMaybeParallelIterator, MaybeParallelSort};
 use rand::Rng;
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 
 /// Fate terminates the physics for a particular entity with a single fate.
 enum Fate {
+    Mutate(Mutation),
     Remove(DeathReason),
     MoveSector,
     DowngradeHq,
@@ -36,12 +42,59 @@ impl World {
         let delta_seconds = delta.to_secs();
         let border_radius = self.radius; // Avoids double borrow.
         let border_radius_squared = self.radius.powi(2);
+        let border_center = self.center;
         let terrain = &self.terrain;
+        let weather = &self.weather;
 
-        // Collected updates (order doesn't matter).
+        // Entities are processed in parallel, so they can't share the world rng.
+        let tick_seed: u64 = self.rng.gen();
+
+        // Collected updates (sorted by index before use, for determinism).
         let terrain_mutations = Mutex::new(Vec::new());
         let barrel_spawns = Mutex::new(Vec::new());
         let reset_flags = Mutex::new(Vec::new());
+        let broken_wires = Mutex::new(Vec::new());
+        let relaunches = Mutex::new(Vec::new());
+        let stale_orders = Mutex::new(Vec::new());
+
+        // Wire-guided weapons and aircraft depend on their launcher, which can't be borrowed while
+        // entities are mutated in parallel. Only those launchers are gathered, since most boats
+        // have neither.
+        let wires = &self.wires;
+        let launcher_indices: HashSet<EntityIndex> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter(|(_, entity)| {
+                entity.data().kind == EntityKind::Aircraft || wires.contains(&entity.id)
+            })
+            .filter_map(|(_, entity)| {
+                if let Status::Alive { entity_index, .. } = entity.borrow_player().data.status {
+                    Some(entity_index)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        let launchers: HashMap<EntityIndex, Launcher> = launcher_indices
+            .into_iter()
+            .map(|index| (index, Launcher::new(&self.entities[index], delta_seconds)))
+            .collect();
+
+        // Likewise, aircraft may be ordered to strike or escort other entities.
+        let squadrons = &self.squadrons;
+        let order_targets: HashSet<EntityId> =
+            squadrons.values().filter_map(|o| o.target()).collect();
+        let order_targets: HashMap<EntityId, Vec2> = if order_targets.is_empty() {
+            HashMap::new()
+        } else {
+            self.entities
+                .par_iter()
+                .into_maybe_parallel_iter()
+                .filter(|(_, entity)| order_targets.contains(&entity.id))
+                .map(|(_, entity)| (entity.id, entity.transform.position))
+                .collect()
+        };
 
         let mut fates: Vec<_> = self
             .entities
@@ -52,7 +105,13 @@ impl World {
                 let data = entity.data();
 
                 if data.lifespan != Ticks::ZERO {
-                    entity.ticks = entity.ticks.saturating_add(delta);
+                    if data.kind == EntityKind::Aircraft {
+                        // Lifespan is fuel for aircraft.
+                        let mut rng = Self::keyed_rng(tick_seed, entity.id.get() as u64);
+                        entity.burn_fuel(delta, &mut rng);
+                    } else {
+                        entity.ticks = entity.ticks.saturating_add(delta);
+                    }
 
                     // Downgrade or die when expired.
                     if entity.ticks > data.lifespan {
@@ -63,6 +122,8 @@ impl World {
                             } else {
                                 Some((index, Fate::DowngradeHq))
                             }
+                        } else if data.kind == EntityKind::Aircraft {
+                            Some((index, Fate::Remove(DeathReason::Ditched)))
                         } else {
                             Some((index, Fate::Remove(DeathReason::Unknown)))
                         };
@@ -85,9 +146,51 @@ impl World {
                 let mut max_speed = data.speed.to_mps();
                 let mut repair_eligible = true;
 
+                if data.kind == EntityKind::Boat {
+                    // Fires and flooding deal damage over time, crediting whoever started them.
+                    if let Some((attacker, weapon, damage)) = entity.update_hazards(delta) {
+                        if entity.ticks.saturating_add(damage) >= data.max_health() {
+                            // Handle the kill before moving, so the sector doesn't change.
+                            return Some((
+                                index,
+                                Fate::Mutate(Mutation::HitBy(attacker, weapon, damage, None)),
+                            ));
+                        }
+                        entity.damage(damage);
+                        repair_eligible = false;
+                    }
+
+                    if entity.extension().flooding.is_some() {
+                        max_speed *= Entity::FLOODING_SPEED;
+                    }
+                }
+
                 match data.kind {
                     EntityKind::Aircraft => {
-                        let position_diff = if let Status::Alive {
+                        // Return to a landing pad when low on fuel, regardless of orders.
+                        let landing_pad = if entity.is_low_on_fuel() {
+                            entity.nearest_landing_pad(&launchers)
+                        } else {
+                            None
+                        };
+
+                        let order = squadrons.get(&entity.id);
+                        let order_target = order.and_then(|order| {
+                            let target = entity.squadron_target(order, &launchers, &order_targets);
+                            if target.is_none() {
+                                // Target is gone, so follow the aim target again.
+                                stale_orders.lock().unwrap().push(entity.id);
+                            }
+                            target
+                        });
+                        let returning =
+                            landing_pad.is_some() || order == Some(&SquadronOrder::Return);
+
+                        let position_diff = if let Some(pad) = landing_pad {
+                            pad.position - entity.transform.position
+                        } else if let Some(order_target) = order_target {
+                            order_target - entity.transform.position
+                        } else if let Status::Alive {
                             aim_target: Some(aim_target),
                             ..
                         } = entity.borrow_player().data.status
@@ -98,10 +201,13 @@ impl World {
                             Vec2::ZERO
                         };
 
-                        entity.guidance.direction_target = Angle::from(position_diff)
-                            + Angle::from_radians(
-                                (entity.hash() - 0.5) * std::f32::consts::PI * 0.25,
-                            );
+                        // Spread out around the target, but not the landing pad.
+                        let spread = if returning {
+                            Angle::ZERO
+                        } else {
+                            Angle::from_radians((entity.hash() - 0.5) * std::f32::consts::PI * 0.25)
+                        };
+                        entity.guidance.direction_target = Angle::from(position_diff) + spread;
                         let distance_squared = position_diff.length_squared();
 
                         let angle_deviation =
@@ -163,8 +269,33 @@ impl World {
                                 _ => {}
                             }
                         }
+
+                        if wires.contains(&entity.id)
+                            && !entity.follow_wire(&launchers, delta_seconds)
+                        {
+                            broken_wires.lock().unwrap().push(entity.id);
+                        }
                     }
                     EntityKind::Boat => {
+                        entity.follow_route(terrain);
+
+                        // Aircraft that returned for fuel are relaunched once rearmed.
+                        if let Some(armament_index) = entity.extension_mut().take_relaunch() {
+                            relaunches.lock().unwrap().push((index, armament_index));
+                        }
+
+                        // Damaged engines can't attain max speed.
+                        max_speed *= entity.extension().engine_factor(data);
+
+                        let altitude = entity.altitude;
+                        let speed = entity.transform.velocity.abs().to_mps();
+                        entity.extension_mut().update_endurance(
+                            data,
+                            altitude,
+                            speed,
+                            delta_seconds,
+                        );
+
                         entity.apply_altitude_target(
                             terrain,
                             Some(entity.extension().altitude_target()),
@@ -186,23 +317,38 @@ impl World {
                             _ => 0.0,
                         };
 
-                        if rand::thread_rng()
+                        if Self::keyed_rng(tick_seed, entity.id.get() as u64)
                             .gen_bool((1.0 - (1.0 - rate).powf(delta_seconds)) as f64)
                         {
                             barrel_spawns
                                 .lock()
                                 .unwrap()
-                                .push(entity.transform.position)
+                                .push((index, entity.transform.position))
                         }
                     }
                     _ => {}
                 }
 
+                if data.kind == EntityKind::Boat {
+                    // Storms slow small boats.
+                    if let Some(weather_max_speed) =
+                        Weather::sample(weather, entity.transform.position).max_speed(data.length)
+                    {
+                        max_speed = max_speed.min(weather_max_speed);
+                    }
+                }
+
                 entity
                     .transform
                     .apply_guidance(data, entity.guidance, max_speed, delta_seconds);
                 entity.transform.do_kinematics(delta_seconds);
 
+                // Ocean currents push entities in the water, but not those in the air.
+                if !entity.altitude.is_airborne() {
+                    entity.transform.position += terrain.current(entity.transform.position)
+                        * (data.current_factor() * delta_seconds);
+                }
+
                 let arctic = entity.transform.position.y >= ARCTIC;
 
                 let collision = entity.collides_with_terrain(terrain, delta_seconds);
@@ -259,10 +405,11 @@ impl World {
                                 breakable,
                             );
 
-                            terrain_mutations
-                                .lock()
-                                .unwrap()
-                                .push((terrain_mutation, is_icebreaker.then_some(index)));
+                            terrain_mutations.lock().unwrap().push((
+                                index,
+                                terrain_mutation,
+                                is_icebreaker,
+                            ));
                         }
                     }
 
@@ -278,7 +425,7 @@ impl World {
                         - terrain
                             .sample(entity.transform.position)
                             .unwrap_or(Altitude::MIN)
-                        - data.draft;
+                        - entity.draft();
 
                     /*
                     println!("{} -> {:?}         ({:?})", terrain
@@ -298,8 +445,8 @@ impl World {
                     }
                 }
 
-                let outside_border =
-                    entity.transform.position.length_squared() > border_radius_squared;
+                let outside_border = entity.transform.position.distance_squared(border_center)
+                    > border_radius_squared;
                 let outside_area =
                     outside_strict_area(entity.entity_type, entity.transform.position);
 
@@ -313,8 +460,8 @@ impl World {
                     // Normal of border facing inwards.
                     let mut normal = Vec2::ZERO;
                     if outside_border {
-                        let n = position.normalize();
-                        *position = n * border_radius;
+                        let n = (*position - border_center).normalize();
+                        *position = border_center + n * border_radius;
                         normal = -n;
                     }
                     if outside_area {
@@ -350,8 +497,9 @@ impl World {
                     if data.sub_kind == EntitySubKind::Dredger {
                         // Dredgers excavate land they come into contact with.
                         terrain_mutations.lock().unwrap().push((
+                            index,
                             TerrainMutation::simple(entity.transform.position, -17.5),
-                            None,
+                            false,
                         ))
                     }
                 }
@@ -364,23 +512,28 @@ impl World {
             })
             .collect();
 
-        for (mutation, award_entity_index) in terrain_mutations.into_inner().unwrap() {
-            if self.terrain.modify(mutation).unwrap_or(false) {
-                if let Some(index) = award_entity_index {
-                    // Terrain actually changed, award some points.
-                    self.entities[index].borrow_player_mut().score += 1;
-                }
+        // Parallel iteration pushes in an arbitrary order. Sort (stably, to preserve the order
+        // within one entity) so that results don't depend on thread scheduling.
+        let mut terrain_mutations = terrain_mutations.into_inner().unwrap();
+        terrain_mutations.sort_by_key(|(index, _, _)| *index);
+
+        for (index, mutation, award) in terrain_mutations {
+            if self.terrain.modify(mutation).unwrap_or(false) && award {
+                // Terrain actually changed, award some points.
+                self.entities[index].borrow_player_mut().score += 1;
             }
         }
 
         // Spawn barrels around oil platforms.
-        let mut rng = rand::thread_rng();
-        for mut position in barrel_spawns.into_inner().unwrap() {
+        let mut barrel_spawns = barrel_spawns.into_inner().unwrap();
+        barrel_spawns.sort_by_key(|(index, _)| *index);
+
+        for (_, mut position) in barrel_spawns {
             const BARREL_RADIUS: f32 = 120.0;
-            position +=
-                rng.gen::<Angle>().to_vec() * rng.gen_range((BARREL_RADIUS / 2.0)..BARREL_RADIUS);
-            let direction = rng.gen();
-            let velocity = Velocity::from_mps(rng.gen_range(10.0..20.0));
+            position += self.rng.gen::<Angle>().to_vec()
+                * self.rng.gen_range((BARREL_RADIUS / 2.0)..BARREL_RADIUS);
+            let direction = self.rng.gen();
+            let velocity = Velocity::from_mps(self.rng.gen_range(10.0..20.0));
             self.spawn_static(
                 EntityType::Barrel,
                 position,
@@ -390,11 +543,31 @@ impl World {
             );
         }
 
+        // Relaunch aircraft (doesn't affect indices, other than adding new ones at the end).
+        let mut relaunches = relaunches.into_inner().unwrap();
+        relaunches.sort_by_key(|(index, _)| *index);
+
+        for (index, armament_index) in relaunches {
+            Mutation::Relaunch(armament_index).apply(self, index, delta, true);
+        }
+
+        for id in stale_orders.into_inner().unwrap() {
+            self.squadrons.remove(&id);
+        }
+
+        // Broken wires can't be reconnected, even if the launcher slows down or closes the distance.
+        for id in broken_wires.into_inner().unwrap() {
+            self.wires.remove(&id);
+        }
+
         // Sorted in reverse to remove correctly.
         fates.maybe_par_sort_unstable_by(|a, b| b.0.cmp(&a.0));
 
         for (index, fate) in fates {
             match fate {
+                Fate::Mutate(mutation) => {
+                    mutation.apply(self, index, delta, true);
+                }
                 Fate::Remove(reason) => {
                     self.remove(index, reason);
                 }
@@ -426,17 +599,280 @@ impl World {
 
 #[cfg(test)]
 mod tests {
+    use crate::entities::EntityIndex;
     use crate::entity::Entity;
+    use crate::player::Status;
     use crate::world::World;
+    use crate::world_test::test_bot;
     use crate::Server;
+    use common::angle::Angle;
+    use common::death_reason::DeathReason;
     use common::entity::{EntityKind, EntityType};
     use common::terrain::Terrain;
     use common::ticks::Ticks;
+    use common::velocity::Velocity;
     use core_protocol::id::PlayerId;
     use game_server::player::{PlayerData, PlayerTuple};
+    use glam::{vec2, Vec2};
     use std::num::NonZeroU32;
     use std::sync::Arc;
 
+    /// Returns whether a torpedo's wire survives a tick, given what its launcher does.
+    fn wire_intact(speed: f32, turn: Angle, distance: f32, launcher_dies: bool) -> bool {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+
+        let owner = test_bot(2);
+        let mut launcher = Entity::new(EntityType::Ohio, Some(Arc::clone(&owner)));
+        launcher.transform.velocity = Velocity::from_mps(speed);
+        launcher.guidance.velocity_target = Velocity::from_mps(speed);
+        launcher.guidance.direction_target = turn;
+        assert!(world.spawn_here_or_nearby(launcher, 0.0, None).is_some());
+
+        let mut torpedo = Entity::new(EntityType::Mark48, Some(Arc::clone(&owner)));
+        torpedo.transform.position = vec2(distance, 0.0);
+        let id = world.spawn_here_or_nearby(torpedo, 0.0, None).unwrap();
+        world.wires.insert(id);
+
+        if launcher_dies {
+            let index = owner
+                .borrow_player()
+                .data
+                .status
+                .get_entity_index()
+                .unwrap();
+            world.remove(index, DeathReason::Unknown);
+        }
+
+        world.physics(Ticks::ONE);
+        world.wires.contains(&id)
+    }
+
+    #[test]
+    fn wire() {
+        assert!(wire_intact(2.0, Angle::ZERO, 200.0, false));
+        // Launcher too fast.
+        assert!(!wire_intact(12.0, Angle::ZERO, 200.0, false));
+        // Launcher turning too fast.
+        assert!(!wire_intact(2.0, Angle::from_degrees(90.0), 200.0, false));
+        assert!(wire_intact(2.0, Angle::from_degrees(0.1), 200.0, false));
+        // Torpedo too far.
+        assert!(!wire_intact(2.0, Angle::ZERO, 1500.0, false));
+        // Launcher destroyed.
+        assert!(!wire_intact(2.0, Angle::ZERO, 200.0, true));
+    }
+
+    /// Spawns a boat at the origin whose helicopter is in flight, returning its armament index.
+    fn world_with_carrier(player: &Arc<PlayerTuple<Server>>) -> (World, usize) {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+
+        let boat = Entity::new(EntityType::ArleighBurke, Some(Arc::clone(player)));
+        let armament = boat
+            .data()
+            .armaments
+            .iter()
+            .position(|a| a.entity_type == EntityType::Seahawk)
+            .unwrap();
+        assert!(world.spawn_here_or_nearby(boat, 0.0, None).is_some());
+        world.entities[carrier_index(player)].consume_armament(armament);
+        (world, armament)
+    }
+
+    fn carrier_index(player: &Arc<PlayerTuple<Server>>) -> EntityIndex {
+        player
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap()
+    }
+
+    /// Spawns a helicopter with a fraction of its fuel remaining.
+    fn spawn_seahawk(
+        world: &mut World,
+        player: &Arc<PlayerTuple<Server>>,
+        position: Vec2,
+        fuel: f32,
+    ) {
+        let mut aircraft = Entity::new(EntityType::Seahawk, Some(Arc::clone(player)));
+        aircraft.transform.position = position;
+        aircraft.ticks = Ticks::from_secs(aircraft.data().lifespan.to_secs() * (1.0 - fuel));
+        assert!(world.spawn_here_or_nearby(aircraft, 0.0, None).is_some());
+    }
+
+    fn find_seahawk(world: &World) -> Option<EntityIndex> {
+        world
+            .entities
+            .iter_radius(Vec2::ZERO, 1000.0)
+            .find(|(_, e)| e.entity_type == EntityType::Seahawk)
+            .map(|(index, _)| index)
+    }
+
+    #[test]
+    fn low_fuel_returns() {
+        let player = test_bot(0);
+        let (mut world, armament) = world_with_carrier(&player);
+        let position = vec2(0.0, 500.0);
+        spawn_seahawk(&mut world, &player, position, 0.2);
+
+        let boat = &world.entities[carrier_index(&player)];
+        let pad = boat.transform
+            + boat
+                .data()
+                .armament_transform(&boat.extension().turrets, armament);
+
+        world.physics(Ticks::ONE);
+        let aircraft = &world.entities[find_seahawk(&world).unwrap()];
+        let error = aircraft.guidance.direction_target - Angle::from(pad.position - position);
+        assert!(error.abs() < Angle::from_degrees(1.0), "{:?}", error);
+    }
+
+    #[test]
+    fn landed_relaunched() {
+        let player = test_bot(0);
+        let (mut world, armament) = world_with_carrier(&player);
+        spawn_seahawk(&mut world, &player, vec2(0.0, 500.0), 0.2);
+
+        let index = find_seahawk(&world).unwrap();
+        world.remove(index, DeathReason::Landing(armament));
+        assert!(find_seahawk(&world).is_none());
+
+        // Long enough to rearm.
+        for _ in 0..Ticks::from_whole_secs(6).0 {
+            world.physics(Ticks::ONE);
+        }
+        assert!(find_seahawk(&world).is_some(), "should have relaunched");
+        let boat = &world.entities[carrier_index(&player)];
+        assert_eq!(boat.extension().reloads[armament], Ticks::MAX);
+    }
+
+    #[test]
+    fn ditched() {
+        let player = test_bot(0);
+        let (mut world, armament) = world_with_carrier(&player);
+        spawn_seahawk(&mut world, &player, vec2(0.0, 500.0), 0.0);
+
+        for _ in 0..Ticks::from_whole_secs(5).0 {
+            world.physics(Ticks::ONE);
+            if find_seahawk(&world).is_none() {
+                break;
+            }
+        }
+        assert!(find_seahawk(&world).is_none(), "should have ditched");
+
+        // Unlike landing, ditching doesn't rearm quickly (or relaunch).
+        let boat = &world.entities[carrier_index(&player)];
+        let reload = boat.data().armaments[armament].reload();
+        assert_eq!(boat.extension().reloads[armament], reload);
+    }
+
+    /// Flooded boats are slower.
+    #[test]
+    fn flooding_speed() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+
+        let attacker = test_bot(1);
+        let players = [test_bot(2), test_bot(3)];
+        for (i, player) in players.iter().enumerate() {
+            let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(player)));
+            entity.transform.position = vec2(0.0, i as f32 * 500.0);
+            entity.guidance.velocity_target = entity.data().speed;
+            assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+        }
+
+        // Spawning clears hazards, so start flooding afterwards.
+        let index = players[0]
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        world.entities[index].start_hazard(&attacker, EntityType::Mark18);
+
+        // Long enough to reach full speed, but not to pump out the flooding.
+        for _ in 0..100 {
+            world.physics(Ticks::ONE);
+        }
+
+        let speeds: Vec<f32> = players
+            .iter()
+            .map(|player| {
+                let index = player
+                    .borrow_player()
+                    .data
+                    .status
+                    .get_entity_index()
+                    .unwrap();
+                world.entities[index].transform.velocity.to_mps()
+            })
+            .collect();
+
+        let max_speed = EntityType::Yamato.data().speed.to_mps();
+        let flooded_speed = max_speed * Entity::FLOODING_SPEED;
+        assert!(
+            speeds[0] <= flooded_speed + 0.1,
+            "{} <= {}",
+            speeds[0],
+            flooded_speed
+        );
+        assert!(
+            speeds[1] > flooded_speed + 0.1,
+            "{} > {}",
+            speeds[1],
+            flooded_speed
+        );
+    }
+
+    /// Whoever started a fire is credited for the kill it results in.
+    #[test]
+    fn hazard_kill_credit() {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+
+        // Bots, so they have aliases without clients.
+        let attacker = test_bot(2);
+        let victim = test_bot(3);
+        let mut entity = Entity::new(EntityType::Yamato, Some(Arc::clone(&victim)));
+        entity.damage(entity.data().max_health() - Ticks::ONE);
+        assert!(world.spawn_here_or_nearby(entity, 0.0, None).is_some());
+        let index = victim
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        world.entities[index].start_hazard(&attacker, EntityType::Mark8);
+
+        world.physics(Ticks::ONE);
+
+        let alias = attacker.borrow_player().alias();
+        match &victim.borrow_player().data.status {
+            Status::Dead { reason, .. } => {
+                assert_eq!(reason, &DeathReason::Weapon(alias, EntityType::Mark8))
+            }
+            _ => panic!("expected fire to sink the boat"),
+        }
+        assert!(attacker.borrow_player().score > 0);
+    }
+
     /// Tests how long each boat takes to recover from (one tick less than) full damage.
     #[test]
     fn repair_rate() {
@@ -467,7 +903,7 @@ mod tests {
             entity.damage(entity.data().max_health() - Ticks::ONE);
             //entity.damage(Ticks::from_damage(1.0));
             assert!(
-                world.spawn_here_or_nearby(entity, 10000.0, None),
+                world.spawn_here_or_nearby(entity, 10000.0, None).is_some(),
                 "could not spawn {:?}",
                 typ
             );
@@ -14,8 +14,8 @@ use common::ticks;
 use common::ticks::Ticks;
 use common::util::hash_u32_to_f32;
 use common::velocity::Velocity;
-use maybe_parallel_iterator::{IntoMaybeParallelIterator, MaybeParallelSort};
-use rand::{thread_rng, Rng};
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+use rand::Rng;
 use std::sync::Arc;
 use std::sync::Mutex;
 
@@ -63,6 +63,9 @@ impl World {
     pub fn physics_radius(&mut self, delta: Ticks) {
         let delta_seconds = delta.to_secs();
 
+        // Pairs are processed in parallel, so they can't share the world rng.
+        let tick_seed: u64 = self.rng.gen();
+
         // TODO: look into lock free data structures.
         let mutations = Mutex::new(Vec::new());
 
@@ -130,8 +133,22 @@ impl World {
                         }
                     };
 
-                    let mutate =
-                        |e: &Entity, m: Mutation| mutations.lock().unwrap().push((get_index(e), m));
+                    // The pair is recorded to order mutations deterministically, regardless of
+                    // which thread pushed them first.
+                    let mutate = |e: &Entity, m: Mutation| {
+                        mutations
+                            .lock()
+                            .unwrap()
+                            .push((get_index(e), (index, other_index), m))
+                    };
+
+                    // Randomness that is deterministic for this pair of entities.
+                    let pair_rng = || {
+                        Self::keyed_rng(
+                            tick_seed,
+                            (entity.id.get() as u64) << 32 | other_entity.id.get() as u64,
+                        )
+                    };
 
                     macro_rules! debug_remove {
                         ($entity:expr, $($arg:tt)*) => {
@@ -195,10 +212,10 @@ impl World {
                                                 target_data.kind == EntityKind::Aircraft || matches!(target_data.sub_kind, EntitySubKind::Missile | EntitySubKind::Rocket | EntitySubKind::RocketTorpedo)
                                             },
                                             EntitySubKind::Torpedo => {
-                                                target_data.kind == EntityKind::Boat || target_data.kind == EntityKind::Decoy
+                                                target_data.kind == EntityKind::Boat || target_data.sub_kind == EntitySubKind::Sonar
                                             },
                                             EntitySubKind::Missile => {
-                                                target_data.kind == EntityKind::Boat && weapon.altitude_overlapping(target)
+                                                (target_data.kind == EntityKind::Boat && weapon.altitude_overlapping(target)) || target_data.sub_kind == EntitySubKind::Chaff
                                             }
                                             _ => {
                                                 target_data.kind == EntityKind::Boat
@@ -314,20 +331,6 @@ impl World {
                                         }
                                     }
                                 }
-
-                                // Automatic anti-aircraft has a chance of killing aircraft.
-                                if target_data.anti_aircraft > 0.0 && weapon_data.kind == EntityKind::Aircraft && target_data.kind == EntityKind::Boat {
-                                    let d2 = weapon.transform.position.distance_squared(target.transform.position);
-                                    let r2 = target_data.anti_aircraft_range().powi(2);
-
-                                    // In range of aa.
-                                    if d2 <= r2 {
-                                        let chance = (1.0 - d2/r2) * target_data.anti_aircraft * delta.to_secs();
-                                        if thread_rng().gen_bool((chance as f64).clamp(0.0, 1.0)) {
-                                            debug_remove!(weapon, "shot down");
-                                        }
-                                    }
-                                }
                             }
                         } else if boats.len() == 1 && weapons.len() == 1 && boats[0].has_same_player(weapons[0]) &&
                             weapons[0].data().kind == EntityKind::Aircraft &&
@@ -430,6 +433,7 @@ impl World {
                                 let front_d2 = front_pos.distance_squared(boat.transform.position);
                                 damage *= collision_multiplier(front_d2, data.radius.powi(2), data.sub_kind == EntitySubKind::Submarine);
                                 damage *= boat.extension().spawn_protection();
+                                damage *= data.resistance_to_ram();
 
                                 // Boats that do more ram damage take less recoil.
                                 if data.ram_damage != 1.0 {
@@ -495,6 +499,7 @@ impl World {
                                 Arc::clone(weapons[0].player.as_ref().unwrap()),
                                 weapons[0].entity_type,
                                 damage,
+                                Some(weapons[0].transform.position),
                             ),
                         );
                         debug_remove!(weapons[0], "hit");
@@ -509,7 +514,7 @@ impl World {
                     {
                         // Coins get consumed every other collectible passes under.
                         if obstacles[0].entity_type == EntityType::OilPlatform && collectibles[0].player.is_some() {
-                            if rand::thread_rng().gen_bool(0.1) {
+                            if pair_rng().gen_bool(0.1) {
                                 mutate(obstacles[0], Mutation::UpgradeHq);
                             }
 
@@ -547,24 +552,29 @@ impl World {
 
         let mut mutations = mutations.into_inner().unwrap();
 
-        // Sort by reverse EntityIndex while prioritizing Mutation ordering.
-        mutations.maybe_par_sort_unstable_by(|a, b| {
+        // Sort by reverse EntityIndex while prioritizing Mutation ordering. Ties are broken by
+        // the pair of entities that caused the mutation, and then by the order they were pushed
+        // (stable sort), so the result doesn't depend on thread scheduling.
+        mutations.sort_by(|a, b| {
             b.0.cmp(&a.0).then_with(|| {
-                b.1.absolute_priority().cmp(&a.1.absolute_priority()).then(
-                    b.1.relative_priority()
-                        .partial_cmp(&a.1.relative_priority())
-                        .unwrap(),
-                )
+                b.2.absolute_priority()
+                    .cmp(&a.2.absolute_priority())
+                    .then(
+                        b.2.relative_priority()
+                            .partial_cmp(&a.2.relative_priority())
+                            .unwrap(),
+                    )
+                    .then(a.1.cmp(&b.1))
             })
         });
 
         // Apply mutations (already reversed).
         let mut skip = None;
         let mut iter = mutations.into_iter().peekable();
-        while let Some((index, mutation)) = iter.next() {
+        while let Some((index, _, mutation)) = iter.next() {
             let last_of_mutation_type = iter
                 .peek()
-                .map(|(next_index, next_mutation)| {
+                .map(|(next_index, _, next_mutation)| {
                     *next_index != index
                         || std::mem::discriminant(&mutation)
                             != std::mem::discriminant(next_mutation)
@@ -591,8 +601,14 @@ fn collision_multiplier(d2: f32, r2: f32, is_sub: bool) -> f32 {
 mod tests {
     use crate::entity::Entity;
     use crate::world::World;
+    use crate::world_test::test_bot;
+    use common::angle::Angle;
     use common::entity::EntityType;
+    use common::terrain::Terrain;
     use common::ticks::Ticks;
+    use common::velocity::Velocity;
+    use glam::{vec2, Vec2};
+    use std::sync::Arc;
 
     #[test]
     fn test_minimum_scan_radius() {
@@ -610,4 +626,48 @@ mod tests {
 
         println!("{:?}", minimum_scan_radii);
     }
+
+    #[test]
+    fn chaff_seduces_missile() {
+        unsafe { EntityType::init() }
+
+        let mut world = World::new(2000.0);
+        world.terrain = Terrain::new();
+
+        let defender = test_bot(0);
+        let boat_position = vec2(400.0, -100.0);
+        let mut boat = Entity::new(EntityType::Komar, Some(Arc::clone(&defender)));
+        boat.transform.position = boat_position;
+        assert!(world.spawn_here_or_nearby(boat, 0.0, None).is_some());
+
+        let mut missile = Entity::new(EntityType::Harpoon, Some(test_bot(1)));
+        missile.transform.velocity = Velocity::from_mps(200.0);
+        // Past the point where its sensor activates.
+        missile.ticks = Ticks::from_secs(1.5);
+        assert!(world.spawn_here_or_nearby(missile, 0.0, None).is_some());
+
+        let direction_target = |world: &World| {
+            world
+                .entities
+                .iter_radius(Vec2::ZERO, 1.0)
+                .find(|(_, e)| e.entity_type == EntityType::Harpoon)
+                .unwrap()
+                .1
+                .guidance
+                .direction_target
+        };
+
+        world.physics_radius(Ticks::ONE);
+        let error = direction_target(&world) - Angle::from(boat_position);
+        assert!(error.abs() < Angle::from_degrees(5.0), "{:?}", error);
+
+        let chaff_position = vec2(150.0, 50.0);
+        let mut chaff = Entity::new(EntityType::Srboc, Some(defender));
+        chaff.transform.position = chaff_position;
+        assert!(world.spawn_here_or_nearby(chaff, 0.0, None).is_some());
+
+        world.physics_radius(Ticks::ONE);
+        let error = direction_target(&world) - Angle::from(chaff_position);
+        assert!(error.abs() < Angle::from_degrees(5.0), "{:?}", error);
+    }
 }
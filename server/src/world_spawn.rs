@@ -14,7 +14,8 @@ use common::world::distance_to_soft_area_border;
 use common_util::range::gen_radius;
 use glam::Vec2;
 use log::{info, warn};
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::Rng;
 use std::time::Instant;
 
 impl World {
@@ -33,7 +34,7 @@ impl World {
     ///
     /// An optional exclusion zone can block spawning.
     ///
-    /// Returns true if spawning successful, false if failed.
+    /// Returns the id of the spawned entity if successful, None if failed.
     ///
     /// INVARIANT: Will not affect any entity indices except adding a new one at the end.
     pub fn spawn_here_or_nearby(
@@ -41,11 +42,10 @@ impl World {
         mut entity: Entity,
         initial_radius: f32,
         exclusion_zone: Option<Vec2>,
-    ) -> bool {
+    ) -> Option<EntityId> {
         let retry = initial_radius > 0.0;
         if retry {
             let start_time = Instant::now();
-            let mut rng = rand::thread_rng();
             let mut radius = initial_radius.max(1.0);
             let center = entity.transform.position;
             let (max_attempts, mut threshold): (u32, f32) = if entity.is_boat() {
@@ -73,9 +73,9 @@ impl World {
                 || !self.can_spawn(&entity, threshold, max_distance_from_center)
             {
                 // Pick a new position
-                let position = gen_radius(&mut rng, radius);
+                let position = gen_radius(&mut self.rng, radius);
                 entity.transform.position = center + position;
-                entity.transform.direction = rng.gen();
+                entity.transform.direction = self.rng.gen();
 
                 radius = (radius * 1.05).min(max_distance_from_center);
                 threshold = 0.005 + threshold * 0.995; // Approaches 1.0
@@ -130,20 +130,17 @@ impl World {
 
         let t = entity.entity_type;
         let spawned = self.try_spawn(entity);
-        if !spawned {
+        if spawned.is_none() {
             warn!("couldn't spawn {:?}", t);
         }
         spawned
     }
 
-    /// try_spawn attempts to spawn an entity at a position and returns if the entity was spawned.
-    pub fn try_spawn(&mut self, entity: Entity) -> bool {
-        if self.can_spawn(&entity, 1.0, self.radius) {
-            self.add(entity);
-            true
-        } else {
-            false
-        }
+    /// try_spawn attempts to spawn an entity at a position and returns its id if the entity was
+    /// spawned.
+    pub fn try_spawn(&mut self, entity: Entity) -> Option<EntityId> {
+        self.can_spawn(&entity, 1.0, self.radius)
+            .then(|| self.add(entity))
     }
 
     /// Threshold ranges from [1,infinity), and makes the spawning more picky.
@@ -163,7 +160,9 @@ impl World {
             self.radius
         );
 
-        if entity.transform.position.length_squared() > max_distance_from_center.powi(2) {
+        if entity.transform.position.distance_squared(self.center)
+            > max_distance_from_center.powi(2)
+        {
             // Outside world/max radius from center.
             return false;
         }
@@ -261,17 +260,17 @@ impl World {
             self.arena.count(EntityType::OilPlatform) + self.arena.count(EntityType::Hq);
 
         self.spawn_static_amount(
-            |_| Some(EntityType::Crate),
+            |_, _| Some(EntityType::Crate),
             crate_count,
             self.target_count(Self::CRATE_DENSITY),
             ticks.0 as usize * 150,
         );
 
         self.spawn_static_amount(
-            |position| {
+            |position, rng| {
                 Some(if position.y >= common::world::ARCTIC + 300.0 {
                     EntityType::Hq
-                } else if position.y < common::world::ARCTIC && thread_rng().gen_bool(0.2) {
+                } else if position.y < common::world::ARCTIC && rng.gen_bool(0.2) {
                     EntityType::OilPlatform
                 } else {
                     // Fail, to bias against ocean spawns, in favor of arctic.
@@ -286,26 +285,25 @@ impl World {
 
     /// Spawns a certain amount of basic entities, all throughout the world.
     ///
-    /// Takes function to get the exact type of entity to spawn, based on the location.
+    /// Takes function to get the exact type of entity to spawn, based on the location (and the
+    /// world's rng).
     fn spawn_static_amount(
         &mut self,
-        mut get_entity_type: impl FnMut(Vec2) -> Option<EntityType>,
+        mut get_entity_type: impl FnMut(Vec2, &mut StdRng) -> Option<EntityType>,
         current: usize,
         target: usize,
         rate: usize,
     ) {
-        let mut rng = rand::thread_rng();
-
         for _ in 0..target.saturating_sub(current).min(rate) {
-            let position = gen_radius(&mut rng, self.radius);
-            let direction = rng.gen();
+            let position = self.center + gen_radius(&mut self.rng, self.radius);
+            let direction = self.rng.gen();
 
-            if let Some(entity_type) = get_entity_type(position) {
+            if let Some(entity_type) = get_entity_type(position, &mut self.rng) {
                 let lifespan = entity_type.data().lifespan;
 
                 // Randomize lifespan a bit to avoid all spawned entities dying at the same time.
                 let ticks = if lifespan != Ticks::ZERO {
-                    lifespan * (rng.gen::<f32>() * 0.25)
+                    lifespan * (self.rng.gen::<f32>() * 0.25)
                 } else {
                     Ticks::ZERO
                 };
@@ -0,0 +1,114 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::player::Status;
+use crate::world::World;
+use common::entity::EntityId;
+use maybe_parallel_iterator::IntoMaybeParallelIterator;
+
+impl World {
+    /// Drops strike and escort orders whose targets are no longer detected by the boat that gave
+    /// them, so aircraft can't be used to track contacts that can't otherwise be seen. Only the
+    /// boat's own sensors count, not teammates' or sonobuoys' (see `Squadron::apply`).
+    pub fn update_squadrons(&mut self) {
+        let squadrons = &self.squadrons;
+        if squadrons.values().all(|order| order.target().is_none()) {
+            return;
+        }
+
+        let stale: Vec<EntityId> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .filter_map(|(_, aircraft)| {
+                let id = squadrons.get(&aircraft.id)?.target()?;
+                let boat = match aircraft.borrow_player().data.status {
+                    Status::Alive { entity_index, .. } => &self.entities[entity_index],
+                    _ => return Some(aircraft.id),
+                };
+                let detected = self
+                    .entities
+                    .iter_radius(boat.transform.position, boat.data().sensors.max_range())
+                    .find(|(_, target)| target.id == id)
+                    .map_or(false, |(_, target)| {
+                        target.is_friendly(boat) || self.detects(boat, target)
+                    });
+                (!detected).then_some(aircraft.id)
+            })
+            .collect();
+
+        for id in stale {
+            self.squadrons.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::entity::Entity;
+    use crate::world::World;
+    use crate::world_test::test_bot;
+    use crate::Server;
+    use common::death_reason::DeathReason;
+    use common::entity::{EntityId, EntityType};
+    use common::protocol::SquadronOrder;
+    use common::terrain::Terrain;
+    use game_server::player::PlayerTuple;
+    use glam::vec2;
+    use std::sync::Arc;
+
+    /// Spawns a boat whose helicopter is ordered to strike an enemy boat some distance away,
+    /// returning the helicopter's id.
+    fn world_with_strike(owner: &Arc<PlayerTuple<Server>>, distance: f32) -> (World, EntityId) {
+        unsafe {
+            EntityType::init();
+        }
+
+        let mut world = World::new(10000.0);
+        world.terrain = Terrain::new();
+
+        let boat = Entity::new(EntityType::ArleighBurke, Some(Arc::clone(owner)));
+        assert!(world.spawn_here_or_nearby(boat, 0.0, None).is_some());
+
+        let mut enemy = Entity::new(EntityType::Yamato, Some(test_bot(1)));
+        enemy.transform.position = vec2(distance, 0.0);
+        let enemy_id = world.spawn_here_or_nearby(enemy, 0.0, None).unwrap();
+
+        let mut aircraft = Entity::new(EntityType::Seahawk, Some(Arc::clone(owner)));
+        aircraft.transform.position = vec2(0.0, 200.0);
+        let aircraft_id = world.spawn_here_or_nearby(aircraft, 0.0, None).unwrap();
+
+        world
+            .squadrons
+            .insert(aircraft_id, SquadronOrder::Strike(enemy_id));
+        (world, aircraft_id)
+    }
+
+    #[test]
+    fn order_persists() {
+        let (mut world, aircraft) = world_with_strike(&test_bot(0), 600.0);
+        world.update_squadrons();
+        assert!(world.squadrons.contains_key(&aircraft));
+    }
+
+    #[test]
+    fn stale_order_removed() {
+        // Target beyond the boat's sensors.
+        let (mut world, aircraft) = world_with_strike(&test_bot(0), 5000.0);
+        world.update_squadrons();
+        assert!(!world.squadrons.contains_key(&aircraft));
+
+        // Boat that gave the order sank.
+        let owner = test_bot(2);
+        let (mut world, aircraft) = world_with_strike(&owner, 600.0);
+        let index = owner
+            .borrow_player()
+            .data
+            .status
+            .get_entity_index()
+            .unwrap();
+        world.remove(index, DeathReason::Unknown);
+        world.update_squadrons();
+        assert!(!world.squadrons.contains_key(&aircraft));
+    }
+}
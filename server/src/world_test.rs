@@ -1,15 +1,18 @@
 use crate::entities::EntityIndex;
 use crate::entity::Entity;
 use crate::world::World;
+use crate::Server;
 use common::altitude::Altitude;
 use common::entity::EntityKind;
 use common::world::ARCTIC;
+use core_protocol::id::PlayerId;
+use game_server::player::{PlayerData, PlayerTuple};
 use glam::Vec2;
 use image::{Rgba, RgbaImage};
 use imageproc::drawing::{draw_polygon_mut, Blend};
 use imageproc::point::Point;
 use maybe_parallel_iterator::IntoMaybeParallelIterator;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 
 impl World {
     /// Creates a false-color CPU rendering of the world for testing purposes.
@@ -86,24 +89,63 @@ impl World {
 
         canvas.into_inner().unwrap().0
     }
+
+    /// Describes the state of all entities, sorted by id, for the purpose of comparing worlds.
+    /// Debug formatting of floats round-trips, so equal snapshots imply bit-identical state.
+    pub fn test_snapshot(&self) -> Vec<String> {
+        let mut entities: Vec<(u32, String)> = self
+            .entities
+            .par_iter()
+            .into_maybe_parallel_iter()
+            .map(|(_, entity)| {
+                (
+                    entity.id.get(),
+                    format!(
+                        "{:?} {:?} {:?} {:?} {:?}",
+                        entity.entity_type,
+                        entity.transform,
+                        entity.guidance,
+                        entity.altitude,
+                        entity.ticks
+                    ),
+                )
+            })
+            .collect();
+        entities.sort_unstable_by_key(|(id, _)| *id);
+        entities.into_iter().map(|(_, s)| s).collect()
+    }
+}
+
+/// Creates the nth bot player, which doesn't belong to any arena.
+pub fn test_bot(n: usize) -> Arc<PlayerTuple<Server>> {
+    Arc::new(PlayerTuple::new(PlayerData::new(
+        PlayerId::nth_bot(n).unwrap(),
+        None,
+    )))
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::bot::Bot;
     use crate::protocol::AsCommandTrait;
     use crate::world::World;
+    use crate::world_test::test_bot;
     use crate::Server;
+    use common::complete::CompleteTrait;
     use common::entity::{EntityData, EntityType};
     use common::protocol::{Command, Spawn};
+    use common::terrain::TerrainMutation;
     use common::ticks::Ticks;
     use common::util::level_to_score;
     use core_protocol::id::PlayerId;
+    use game_server::game_service::BotAction;
     use game_server::player::{PlayerData, PlayerTuple};
     use glam::Vec2;
     use rand::prelude::IteratorRandom;
     use rand::{thread_rng, Rng};
     use server_util::generate_id::generate_id;
     use std::sync::Arc;
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_render() {
@@ -176,4 +218,132 @@ mod tests {
             .save(format!("test_render_{}.png", player_count))
             .unwrap();
     }
+
+    #[test]
+    fn determinism() {
+        crate::noise::init();
+        unsafe { EntityType::init() };
+
+        const SEED: u64 = 1234;
+        const BOTS: usize = 20;
+
+        let mut worlds: Vec<_> = (0..2)
+            .map(|_| {
+                let world = World::with_seed(World::target_radius(1500f32.powi(2) * 40.0), SEED);
+                let bots: Vec<_> = (0..BOTS)
+                    .map(|i| (test_bot(i), Bot::with_seed(SEED + i as u64)))
+                    .collect();
+                (world, bots)
+            })
+            .collect();
+
+        // Worlds are advanced in lockstep, so that wall-clock time doesn't differ between them.
+        for _ in 0..300 {
+            for (world, bots) in worlds.iter_mut() {
+                for (player, bot) in bots.iter_mut() {
+                    let player_id = player.borrow_player().player_id;
+                    let action = bot.update(
+                        world.get_player_complete(player),
+                        player_id,
+                        world.players_can_spawn(),
+                    );
+                    if let BotAction::Some(command) = action {
+                        let _ = command.as_command().apply(world, player);
+                    }
+                }
+                world.update(Ticks::ONE);
+                world.terrain.pre_update();
+                world.terrain.post_update(Ticks::ONE, &mut world.rng);
+            }
+
+            assert_eq!(worlds[0].0.test_snapshot(), worlds[1].0.test_snapshot());
+        }
+
+        assert!(worlds[0].0.arena.count(EntityType::Crate) > 0);
+
+        // Regeneration of modified terrain must not depend on wall-clock time either.
+        let pos = Vec2::ZERO;
+        let original = worlds[0].0.terrain.sample(pos).unwrap();
+        let amount = if original.0 > 0 { -100.0 } else { 100.0 };
+        let modified: Vec<_> = worlds
+            .iter_mut()
+            .map(|(world, _)| {
+                assert_eq!(
+                    world.terrain.modify(TerrainMutation::simple(pos, amount)),
+                    Some(true)
+                );
+                world.terrain.sample(pos).unwrap()
+            })
+            .collect();
+        assert_eq!(modified[0], modified[1]);
+
+        for _ in 0..30 {
+            for (world, _) in worlds.iter_mut() {
+                world.terrain.pre_update();
+                world
+                    .terrain
+                    .post_update(Ticks::from_whole_secs(60), &mut world.rng);
+            }
+        }
+
+        let regenerated = worlds[0].0.terrain.sample(pos).unwrap();
+        assert_ne!(regenerated, modified[0]);
+        assert_eq!(regenerated, worlds[1].0.terrain.sample(pos).unwrap());
+        assert_eq!(worlds[0].0.rng.gen::<u64>(), worlds[1].0.rng.gen::<u64>());
+    }
+
+    /// Measures the cost of computing what every player can see (see `line_of_sight_benchmark` in
+    /// common for line of sight by itself). Run with
+    /// `cargo test --release -- --ignored outbound_benchmark`.
+    #[test]
+    #[ignore]
+    fn outbound_benchmark() {
+        crate::noise::init();
+        unsafe { EntityType::init() };
+
+        const BOTS: usize = 300;
+        const TICKS: usize = 50;
+
+        let mut world = World::with_seed(World::target_radius(1500f32.powi(2) * BOTS as f32), 1);
+        let mut bots: Vec<_> = (0..BOTS)
+            .map(|i| (test_bot(i), Bot::with_seed(i as u64)))
+            .collect();
+
+        let mut elapsed = Duration::ZERO;
+        let mut contacts = 0;
+
+        for _ in 0..TICKS {
+            let start = Instant::now();
+            contacts += bots
+                .iter()
+                .map(|(player, _)| world.get_player_complete(player).contacts().count())
+                .sum::<usize>();
+            elapsed += start.elapsed();
+
+            for (player, bot) in bots.iter_mut() {
+                let player_id = player.borrow_player().player_id;
+                if let BotAction::Some(command) = bot.update(
+                    world.get_player_complete(player),
+                    player_id,
+                    world.players_can_spawn(),
+                ) {
+                    let _ = command.as_command().apply(&mut world, player);
+                }
+            }
+            world.update(Ticks::ONE);
+        }
+
+        let per_tick = elapsed / TICKS as u32;
+        println!(
+            "{} bots, {} contacts per tick, {:?} per tick",
+            BOTS,
+            contacts / TICKS,
+            per_tick
+        );
+
+        if !cfg!(debug_assertions) {
+            // Ticks are 100ms apart, and outbound is only part of a tick.
+            assert!(per_tick < Duration::from_millis(25), "{:?}", per_tick);
+        }
+    }
 }
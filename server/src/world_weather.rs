@@ -0,0 +1,60 @@
+// SPDX-FileCopyrightText: 2021 Softbear, Inc.
+// SPDX-License-Identifier: AGPL-3.0-or-later
+
+use crate::world::World;
+use common::angle::Angle;
+use common::ticks::Ticks;
+use common::weather::{wind, WeatherCell, WeatherKind};
+use common_util::range::gen_radius;
+use rand::Rng;
+
+impl World {
+    /// Weather cells per square meter.
+    const WEATHER_DENSITY: f32 = 1.0 / (3000.0 * 3000.0);
+
+    /// Drifts weather cells with the wind, replacing those that leave the world with new ones
+    /// upwind.
+    pub fn update_weather(&mut self, delta: Ticks) {
+        let delta_seconds = delta.to_secs();
+        let center = self.center;
+        let radius = self.radius;
+
+        for cell in self.weather.iter_mut() {
+            cell.position += cell.velocity * delta_seconds;
+        }
+
+        self.weather
+            .retain(|cell| cell.position.distance(center) < radius + cell.radius);
+
+        // Fill the whole world initially, to avoid waiting for weather to drift in.
+        let initial = self.weather.is_empty();
+        let target = self.target_count(Self::WEATHER_DENSITY);
+
+        while self.weather.len() < target {
+            let rng = &mut self.rng;
+
+            let kind = match rng.gen_range(0..3) {
+                0 => WeatherKind::Fog,
+                1 => WeatherKind::Rain,
+                _ => WeatherKind::Storm,
+            };
+            let cell_radius = rng.gen_range(400.0..1500.0);
+            let velocity = wind() + gen_radius(rng, 2.0);
+
+            let position = if initial {
+                center + gen_radius(rng, radius)
+            } else {
+                // Straddling the upwind side of the border, so the cell drifts in.
+                let angle = Angle::from(-velocity) + Angle::from_radians(rng.gen_range(-1.4..1.4));
+                center + angle.to_vec() * (radius + cell_radius * 0.5)
+            };
+
+            self.weather.push(WeatherCell {
+                kind,
+                position,
+                velocity,
+                radius: cell_radius,
+            });
+        }
+    }
+}
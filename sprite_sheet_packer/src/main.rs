@@ -9,7 +9,7 @@ mod texture;
 
 use crate::audio::pack_audio_sprite_sheet;
 use crate::texture::{pack_sprite_sheet, EntityPackParams};
-use common::entity::{EntityData, EntityKind, EntityType};
+use common::entity::{EntityData, EntityKind, EntitySubKind, EntityType};
 use common_util::range::map_ranges;
 
 fn main() {
@@ -36,7 +36,14 @@ fn main() {
     pack_sprite_sheet(
         |entity_type| {
             let data: &'static EntityData = entity_type.data();
-            if true {
+            if matches!(
+                data.sub_kind,
+                EntitySubKind::Chaff | EntitySubKind::Jammer | EntitySubKind::Sonobuoy
+            ) {
+                // Chaff is drawn with particles, sonobuoys with circles, and jammers are never
+                // launched.
+                EntityPackParams { width: 0 }
+            } else if true {
                 fn boat_meters_to_pixels(meters: f32) -> f32 {
                     fn f(x: f32) -> f32 {
                         62.0 * x.sqrt()
@@ -134,6 +141,14 @@ fn main() {
             let aspect = data.length / data.width;
             match data.kind {
                 EntityKind::Boat => EntityPackParams { width: 160 },
+                EntityKind::Decoy
+                    if matches!(
+                        data.sub_kind,
+                        EntitySubKind::Chaff | EntitySubKind::Jammer | EntitySubKind::Sonobuoy
+                    ) =>
+                {
+                    EntityPackParams { width: 0 }
+                }
                 EntityKind::Weapon | EntityKind::Decoy | EntityKind::Aircraft => EntityPackParams {
                     width: 120.min((40.0 * aspect) as u32),
                 },